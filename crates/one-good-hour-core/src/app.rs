@@ -1,9 +1,31 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+use crate::clock::{Clock, GapPolicy, SystemClock, GAP_THRESHOLD_MS};
+use crate::control::SessionView;
+use crate::cycle::{Break, CycleConfig, Phase};
+use crate::editor::LineBuffer;
+use crate::export::{self, Exporter, Format};
+use crate::milestone::{default_milestones, Milestone};
+use crate::stats::Stats;
+use crate::store::{
+    merge_notes, note_key, Checkpoint, HistoryStore, MergedNotes, RecoveryFile, SessionSettings,
+};
+use crate::template::Template;
 use crate::types::*;
+use crate::undo::{Command, SessionState, UndoStack};
 
+/// Longest task text, in grapheme clusters.
 const MAX_TODO_LEN: usize = 50;
 
+/// Seconds added or removed by one extend/shorten step.
+pub const TIME_ADJUST_STEP: i64 = 5 * 60;
+
+/// How often a running session is checkpointed when nothing else changed.
+const CHECKPOINT_INTERVAL_MS: i64 = 15_000;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputMode {
     Normal,
@@ -17,6 +39,15 @@ pub enum ModalKind {
     ClearNotes,
     NewSession,
     Help,
+    /// Focus totals, completion rate and streaks over the history.
+    Stats,
+    /// The machine slept (or the clock jumped) for this many seconds while
+    /// the session was running; confirming counts the time as focused.
+    Away { seconds: u64 },
+    /// An unfinished note from an earlier run was found; confirming resumes it.
+    Resume { time_left: u64, tasks: usize },
+    /// Pick a format to save the history to a file in; confirming picks Markdown.
+    Export,
 }
 
 pub struct App {
@@ -24,12 +55,62 @@ pub struct App {
     pub completed_notes: Vec<CompletedNote>,
     pub input_mode: InputMode,
     pub selected_todo: usize,
+    /// Text and cursor of the task being edited.
+    pub editor: LineBuffer,
     pub history_index: Option<usize>,
     pub modal: Option<ModalKind>,
     pub should_quit: bool,
     pub status_message: Option<String>,
     pub sound_pending: bool,
     pub show_history: bool,
+    /// Show the calendar of focus per day over the past year.
+    pub show_heatmap: bool,
+    /// Planned duration in seconds for new sessions.
+    pub session_length: u64,
+    pub cycle: CycleConfig,
+    /// The running break, if any. `None` means the focus phase.
+    pub on_break: Option<Break>,
+    /// Focus blocks finished since the app started (drives long breaks).
+    pub focus_blocks: u32,
+    /// Keep counting up past zero instead of stopping the focus timer.
+    pub allow_overtime: bool,
+    pub gap_policy: GapPolicy,
+    /// Points in a focus session that trigger an alert.
+    pub milestones: Vec<Milestone>,
+    /// The milestone that fired since the frontend last looked.
+    pub milestone_pending: Option<Milestone>,
+    /// Most tasks a note may hold (1 to `MAX_TODO_CAP`).
+    pub todo_cap: usize,
+    /// Seed the next note with the unfinished tasks when a session completes.
+    pub carry_over: bool,
+    /// Undoable user changes.
+    pub undo_stack: UndoStack,
+    /// Where exported history files are written.
+    pub export_dir: PathBuf,
+    /// User templates, offered for copying and export.
+    pub templates: Vec<Template>,
+    /// The template `copy_markdown` uses; `None` copies plain Markdown.
+    pub copy_template: Option<usize>,
+    /// The task as it was when editing started, to record the edit as one change.
+    edit_before: Option<Todo>,
+    /// Where completed notes are saved, if saving is on.
+    store: Option<HistoryStore>,
+    /// The history as last loaded from or saved to `store`, what the next
+    /// save is merged against.
+    saved_notes: Vec<CompletedNote>,
+    /// Where the active note is checkpointed, if recovery is on.
+    recovery: Option<RecoveryFile>,
+    /// What was last checkpointed and when.
+    last_checkpoint: Option<(String, i64)>,
+    /// A session from an earlier run waiting on the `Resume` modal.
+    recovered: Option<Checkpoint>,
+    /// The app this one mirrors (see `show_view`) saves history.
+    mirror_saving: bool,
+    /// The status message of the session this app mirrors, as last shown.
+    mirror_status: Option<String>,
+    clock: Box<dyn Clock>,
+    /// Wall and monotonic readings at the previous tick.
+    last_tick: Option<(i64, i64)>,
 }
 
 impl Default for App {
@@ -39,12 +120,38 @@ impl Default for App {
             completed_notes: Vec::new(),
             input_mode: InputMode::Normal,
             selected_todo: 0,
+            editor: LineBuffer::new("", MAX_TODO_LEN),
             history_index: None,
             modal: None,
             should_quit: false,
             status_message: None,
             sound_pending: false,
             show_history: true,
+            show_heatmap: false,
+            session_length: DEFAULT_DURATION,
+            cycle: CycleConfig::default(),
+            on_break: None,
+            focus_blocks: 0,
+            allow_overtime: false,
+            gap_policy: GapPolicy::default(),
+            milestones: default_milestones(),
+            milestone_pending: None,
+            todo_cap: DEFAULT_TODO_SLOTS,
+            carry_over: false,
+            undo_stack: UndoStack::default(),
+            export_dir: PathBuf::from("."),
+            templates: Vec::new(),
+            copy_template: None,
+            edit_before: None,
+            store: None,
+            saved_notes: Vec::new(),
+            recovery: None,
+            last_checkpoint: None,
+            recovered: None,
+            mirror_saving: false,
+            mirror_status: None,
+            clock: Box::new(SystemClock),
+            last_tick: None,
         }
     }
 }
@@ -54,49 +161,474 @@ impl App {
         Self::default()
     }
 
+    pub fn with_session_length(session_length: u64) -> Self {
+        Self {
+            active_note: ActiveNote::new(session_length),
+            session_length,
+            ..Self::default()
+        }
+    }
+
+    /// Replace the time source (e.g. with a `ManualClock` in tests).
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Load saved history from `store` and keep saving to it.
+    pub fn with_store(mut self, store: HistoryStore) -> Self {
+        self.attach_store(store);
+        self
+    }
+
+    /// Checkpoint the active note to `file` as it changes. If the file holds
+    /// an unfinished note from an earlier run, offer to resume it. While an
+    /// app that is still running holds the file, it's left to that app and
+    /// this run isn't checkpointed at all. Call after `with_clock`.
+    pub fn with_recovery(mut self, file: RecoveryFile) -> Self {
+        match file.load() {
+            Ok(Some(checkpoint)) if checkpoint.is_held_elsewhere() => {
+                self.status_message = Some(format!(
+                    "Session file in use by process {}; recovery is off for this run",
+                    checkpoint.pid
+                ));
+                return self;
+            }
+            // Taken over first, so no other app offers the same note
+            Ok(Some(checkpoint)) => match file.claim(&checkpoint) {
+                Ok(true) if !checkpoint.note.is_blank() => {
+                    let checkpoint = recover(checkpoint, self.now_ms());
+                    let note = &checkpoint.note;
+                    self.modal = Some(ModalKind::Resume {
+                        time_left: note.time_left,
+                        tasks: note.todos.iter().filter(|t| !t.text.is_empty()).count(),
+                    });
+                    self.input_mode = InputMode::Modal;
+                    self.recovered = Some(checkpoint);
+                }
+                Ok(_) => {}
+                Err(e) => self.status_message = Some(format!("Could not save session: {e:#}")),
+            },
+            Ok(None) => {}
+            Err(e) => self.status_message = Some(format!("Could not read saved session: {e:#}")),
+        }
+        self.recovery = Some(file);
+        self
+    }
+
+    /// Take up the session checkpointed in `file` exactly as it was left, a
+    /// running timer still running, and keep checkpointing to it. For
+    /// one-shot commands that each carry the same session one step further.
+    /// Fails while another running app holds the file. Call after
+    /// `with_clock`.
+    pub fn with_session(mut self, file: RecoveryFile) -> anyhow::Result<Self> {
+        if let Some(checkpoint) = file.load()? {
+            if checkpoint.is_held_elsewhere() || !file.claim(&checkpoint)? {
+                anyhow::bail!(
+                    "the session is open in another app (process {}); quit it, \
+                     or run `1gh daemon` to share the session with these commands",
+                    checkpoint.pid
+                );
+            }
+            self.last_checkpoint = Some((checkpoint_key(&checkpoint), checkpoint.saved_at));
+            self.take_up(checkpoint);
+        }
+        self.recovery = Some(file);
+        self.tick();
+        Ok(self)
+    }
+
+    /// The session checkpointed in `file`, only to look at: running
+    /// countdowns show the time left now, but nothing moves on and nothing
+    /// is written. Call after `with_clock`.
+    pub fn peek_session(mut self, file: &RecoveryFile) -> anyhow::Result<Self> {
+        if let Some(checkpoint) = file.load()? {
+            self.take_up(checkpoint);
+        }
+        let now_ms = self.now_ms();
+        let note = &mut self.active_note;
+        if let Some(target) = note.target_time.filter(|_| note.is_running) {
+            let remaining = seconds_until(target, now_ms);
+            note.time_left = remaining.max(0) as u64;
+            if self.allow_overtime {
+                note.overtime = remaining.min(0).unsigned_abs();
+            }
+        }
+        if let Some(b) = self.on_break.as_mut().filter(|b| b.is_running) {
+            if let Some(target) = b.target_time {
+                b.time_left = seconds_until(target, now_ms).max(0) as u64;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Carry on the session in `checkpoint`, with its settings if it has them.
+    fn take_up(&mut self, checkpoint: Checkpoint) {
+        self.active_note = checkpoint.note;
+        self.on_break = checkpoint.on_break;
+        self.focus_blocks = checkpoint.focus_blocks;
+        if let Some(settings) = checkpoint.settings {
+            self.cycle = settings.cycle;
+            self.allow_overtime = settings.allow_overtime;
+            self.gap_policy = settings.gap_policy;
+            self.milestones = settings.milestones;
+            self.todo_cap = settings.todo_cap;
+            self.carry_over = settings.carry_over;
+        }
+    }
+
+    fn now_ms(&self) -> i64 {
+        self.clock.now_ms()
+    }
+
+    /// An empty note for the next session.
+    fn fresh_note(&self) -> ActiveNote {
+        ActiveNote::with_slots(self.session_length, DEFAULT_TODO_SLOTS.min(self.todo_cap))
+    }
+
     // Timer controls
 
+    pub fn phase(&self) -> Phase {
+        self.on_break.as_ref().map_or(Phase::Focus, |b| b.phase)
+    }
+
+    /// Seconds left in the current phase.
+    pub fn phase_time_left(&self) -> u64 {
+        self.on_break
+            .as_ref()
+            .map_or(self.active_note.time_left, |b| b.time_left)
+    }
+
+    /// Fraction of the current phase already used, in [0.0, 1.0].
+    pub fn phase_progress(&self) -> f64 {
+        self.on_break
+            .as_ref()
+            .map_or_else(|| self.active_note.progress(), |b| b.progress())
+    }
+
+    /// Countdown text for the current phase, or "+m:ss" once in overtime.
+    pub fn timer_display(&self) -> String {
+        if self.on_break.is_none() && self.active_note.overtime > 0 {
+            format_overtime(self.active_note.overtime)
+        } else {
+            format_time(self.phase_time_left())
+        }
+    }
+
+    pub fn is_timer_running(&self) -> bool {
+        self.on_break
+            .as_ref()
+            .map_or(self.active_note.is_running, |b| b.is_running)
+    }
+
     pub fn toggle_timer(&mut self) {
+        let now_ms = self.now_ms();
+        if let Some(b) = self.on_break.as_mut() {
+            if b.is_running {
+                b.is_running = false;
+                b.target_time = None;
+            } else if b.time_left > 0 {
+                b.is_running = true;
+                b.target_time = Some(now_ms.saturating_add(secs_to_ms(b.time_left)));
+            }
+            return;
+        }
+
         if self.active_note.is_running {
             // Pause
             self.active_note.is_running = false;
             self.active_note.target_time = None;
+            self.active_note.end_run(now_ms);
         } else {
-            // Play — guard: must have time left
-            if self.active_note.time_left == 0 {
+            // Play — guard: must have time left unless overtime is allowed
+            if self.active_note.time_left == 0 && !self.allow_overtime {
                 return;
             }
             self.active_note.is_running = true;
-            let now_ms = chrono::Utc::now().timestamp_millis();
-            self.active_note.target_time =
-                Some(now_ms + (self.active_note.time_left as i64 * 1000));
+            // In overtime the target lies in the past by the overtime already counted
+            let offset_ms = secs_to_ms(self.active_note.time_left)
+                .saturating_sub(secs_to_ms(self.active_note.overtime));
+            self.active_note.target_time = Some(now_ms.saturating_add(offset_ms));
+            self.active_note.start_run(now_ms);
         }
     }
 
     pub fn reset_timer(&mut self) {
-        self.active_note.time_left = MAX_TIME;
+        if let Some(b) = self.on_break.as_mut() {
+            *b = Break::new(b.phase, b.duration);
+            return;
+        }
+        self.active_note.time_left = self.active_note.duration;
+        self.active_note.overtime = 0;
+        self.active_note.is_running = false;
+        self.active_note.target_time = None;
+        self.active_note.runs.clear();
+        for todo in &mut self.active_note.todos {
+            todo.time_spent = 0;
+        }
+    }
+
+    /// Change the planned length of the active session (and of new sessions).
+    /// Time already spent is kept; blocked while the timer is running.
+    pub fn set_session_length(&mut self, seconds: u64) {
+        if self.active_note.is_running || seconds == 0 {
+            return;
+        }
+        let elapsed = self.active_note.elapsed().saturating_add(self.active_note.overtime);
+        self.session_length = seconds;
+        self.active_note.duration = seconds;
+        self.active_note.time_left = seconds.saturating_sub(elapsed);
+        self.active_note.overtime = elapsed.saturating_sub(seconds);
+        self.status_message = Some(format!("Session length: {} min", seconds / 60));
+    }
+
+    /// Add (positive) or remove (negative) time from the active focus session,
+    /// running or not. Time already spent can't be removed, so shortening stops
+    /// at the current position and the session ends on the next tick.
+    pub fn adjust_time(&mut self, seconds: i64) {
+        if self.on_break.is_some() {
+            return;
+        }
+        let now_ms = self.now_ms();
+        let note = &mut self.active_note;
+        let seconds = seconds
+            .max(-(note.time_left as i64))
+            .max(1 - note.duration as i64);
+        if seconds == 0 {
+            return;
+        }
+        let total = note.elapsed() + note.overtime;
+        note.duration = (note.duration as i64 + seconds) as u64;
+        note.time_left = note.duration.saturating_sub(total);
+        note.overtime = total.saturating_sub(note.duration);
+        if let Some(target) = note.target_time.as_mut() {
+            *target += seconds * 1000;
+        }
+        note.adjustments.push(TimeAdjustment { at: now_ms, seconds });
+        self.status_message = Some(format!("{} this session", format_adjustment(seconds)));
+    }
+
+    pub fn extend_session(&mut self) {
+        self.adjust_time(TIME_ADJUST_STEP);
+    }
+
+    pub fn shorten_session(&mut self) {
+        self.adjust_time(-TIME_ADJUST_STEP);
+    }
+
+    /// Step to the next entry in `DURATION_PRESETS`, wrapping around.
+    pub fn cycle_session_length(&mut self) {
+        let current = self.active_note.duration;
+        let next = DURATION_PRESETS
+            .iter()
+            .copied()
+            .find(|&d| d > current)
+            .unwrap_or(DURATION_PRESETS[0]);
+        self.set_session_length(next);
+    }
+
+    /// Compare wall-clock and monotonic progress since the previous tick.
+    /// Small drift (NTP, manual clock changes) is folded into the targets so
+    /// countdowns follow the monotonic clock; a large forward jump is returned
+    /// as a gap along with the wall time of the previous tick.
+    fn observe_clock(&mut self) -> Option<(i64, i64)> {
+        let now = (self.clock.now_ms(), self.clock.monotonic_ms());
+        let (prev_wall, prev_mono) = self.last_tick.replace(now)?;
+        let drift = (now.0 - prev_wall) - (now.1 - prev_mono);
+        if drift > GAP_THRESHOLD_MS {
+            return Some((drift, prev_wall));
+        }
+        if drift != 0 {
+            if let Some(target) = self.active_note.target_time.as_mut() {
+                *target += drift;
+            }
+            if let Some(target) = self.on_break.as_mut().and_then(|b| b.target_time.as_mut()) {
+                *target += drift;
+            }
+        }
+        None
+    }
+
+    /// Apply `gap_policy` to a running focus session. Breaks always count the gap.
+    fn handle_gap(&mut self, gap_ms: i64, last_wall: i64) {
+        if self.on_break.is_some()
+            || !self.active_note.is_running
+            || self.gap_policy == GapPolicy::Count
+        {
+            return;
+        }
+        // Stop where the last tick left off
         self.active_note.is_running = false;
         self.active_note.target_time = None;
+        self.active_note.end_run(last_wall);
+        if self.gap_policy != GapPolicy::Ask {
+            return;
+        }
+        let seconds = gap_ms as u64 / 1000;
+        if self.modal.is_some() {
+            // The question already open stays; the session stays paused
+            self.status_message = Some(format!("Paused after {} away", format_time(seconds)));
+            return;
+        }
+        if matches!(self.input_mode, InputMode::Editing(_)) {
+            self.stop_editing();
+        }
+        self.modal = Some(ModalKind::Away { seconds });
+        self.input_mode = InputMode::Modal;
+    }
+
+    /// Resume after an `Away` prompt, counting the gap as focused time.
+    fn count_away(&mut self) {
+        let now_ms = self.now_ms();
+        let Some(run) = self.active_note.runs.last_mut() else {
+            return;
+        };
+        // The run picks up where it stopped, so the countdown takes off
+        // everything since then, the wait on the prompt too
+        let away_ms = run.ended_at.map_or(0, |ended_at| now_ms - ended_at);
+        run.ended_at = None;
+        let offset_ms = secs_to_ms(self.active_note.time_left)
+            .saturating_sub(secs_to_ms(self.active_note.overtime))
+            .saturating_sub(away_ms);
+        self.active_note.is_running = true;
+        self.active_note.target_time = Some(now_ms + offset_ms);
+        self.tick();
+    }
+
+    pub fn cycle_gap_policy(&mut self) {
+        self.gap_policy = self.gap_policy.next();
+        self.status_message = Some(format!("After sleep: {}", self.gap_policy.label()));
     }
 
     pub fn tick(&mut self) {
+        self.tick_phases();
+        self.checkpoint();
+    }
+
+    fn tick_phases(&mut self) {
+        if let Some((gap_ms, last_wall)) = self.observe_clock() {
+            self.handle_gap(gap_ms, last_wall);
+        }
+        if self.on_break.is_some() {
+            self.tick_break();
+            return;
+        }
         if !self.active_note.is_running {
             return;
         }
+        let worked_before = self.active_note.elapsed() + self.active_note.overtime;
+        self.tick_focus();
+        let worked = self.active_note.elapsed() + self.active_note.overtime;
+        self.active_note.credit_active_todo(worked.saturating_sub(worked_before));
+    }
+
+    fn tick_focus(&mut self) {
         if let Some(target) = self.active_note.target_time {
-            let now_ms = chrono::Utc::now().timestamp_millis();
-            let remaining = ((target - now_ms) as f64 / 1000.0).ceil() as i64;
+            let remaining = seconds_until(target, self.now_ms());
+            let before = self.active_note.elapsed();
             if remaining > 0 {
                 self.active_note.time_left = remaining as u64;
+                self.check_milestones(before);
+            } else if self.allow_overtime {
+                // Keep running; overtime replaces the automatic break
+                if self.active_note.time_left > 0 {
+                    self.play_sound();
+                }
+                self.active_note.time_left = 0;
+                self.active_note.overtime = remaining.unsigned_abs();
             } else {
                 self.active_note.time_left = 0;
                 self.active_note.is_running = false;
                 self.active_note.target_time = None;
+                self.active_note.end_run(target);
+                self.play_sound();
+                self.start_break();
+            }
+        }
+    }
+
+    /// Fire the latest milestone crossed since `before` seconds elapsed.
+    fn check_milestones(&mut self, before: u64) {
+        let duration = self.active_note.duration;
+        let after = self.active_note.elapsed();
+        let crossed = self
+            .milestones
+            .iter()
+            .copied()
+            .filter_map(|m| m.at_elapsed(duration).map(|at| (at, m)))
+            .filter(|&(at, _)| before < at && at <= after)
+            .max_by_key(|&(at, _)| at);
+        if let Some((_, milestone)) = crossed {
+            self.milestone_pending = Some(milestone);
+            self.status_message = Some(milestone.label());
+        }
+    }
+
+    fn tick_break(&mut self) {
+        let now_ms = self.now_ms();
+        let Some(b) = self.on_break.as_mut() else {
+            return;
+        };
+        if !b.is_running {
+            return;
+        }
+        if let Some(target) = b.target_time {
+            let remaining = seconds_until(target, now_ms);
+            if remaining > 0 {
+                b.time_left = remaining as u64;
+            } else {
                 self.play_sound();
+                self.end_break();
             }
         }
     }
 
+    /// Move from a finished focus block into the next break.
+    fn start_break(&mut self) {
+        self.focus_blocks += 1;
+        let phase = self.cycle.break_after(self.focus_blocks);
+        let length = self.cycle.length_of(phase);
+        if length == 0 {
+            return;
+        }
+        self.on_break = Some(Break::new(phase, length));
+        if self.cycle.auto_start {
+            self.toggle_timer();
+        }
+    }
+
+    /// Return to the focus phase, auto-starting it if the note has time left.
+    fn end_break(&mut self) {
+        self.on_break = None;
+        if self.cycle.auto_start && self.active_note.time_left > 0 {
+            self.toggle_timer();
+        }
+    }
+
+    pub fn skip_break(&mut self) {
+        if self.on_break.is_some() {
+            self.end_break();
+        }
+    }
+
+    pub fn toggle_overtime(&mut self) {
+        self.allow_overtime = !self.allow_overtime;
+        let state = if self.allow_overtime { "on" } else { "off" };
+        self.status_message = Some(format!("Overtime: {state}"));
+    }
+
+    pub fn toggle_carry_over(&mut self) {
+        self.carry_over = !self.carry_over;
+        let state = if self.carry_over { "on" } else { "off" };
+        self.status_message = Some(format!("Carry unfinished tasks: {state}"));
+    }
+
+    pub fn toggle_auto_start(&mut self) {
+        self.cycle.auto_start = !self.cycle.auto_start;
+        let state = if self.cycle.auto_start { "on" } else { "off" };
+        self.status_message = Some(format!("Auto-start next phase: {state}"));
+    }
+
     // Todo operations
 
     pub fn move_selection_down(&mut self) {
@@ -108,33 +640,118 @@ impl App {
     }
 
     pub fn start_editing(&mut self) {
+        let text = &self.active_note.todos[self.selected_todo].text;
+        self.editor = LineBuffer::new(text, MAX_TODO_LEN);
+        self.edit_before = Some(self.active_note.todos[self.selected_todo].clone());
         self.input_mode = InputMode::Editing(self.selected_todo);
     }
 
     pub fn stop_editing(&mut self) {
+        let before = self.edit_before.take();
+        if let (InputMode::Editing(index), Some(before)) = (self.input_mode.clone(), before) {
+            // Only the text is the edit's; time credited meanwhile isn't
+            let after = Todo {
+                text: self.active_note.todos[index].text.clone(),
+                ..before.clone()
+            };
+            if after.text != before.text {
+                match self.undo_stack.last_mut() {
+                    // Typing into a freshly added row belongs to the add
+                    Some(Command::InsertTodo { index: i, todo }) if *i == index => *todo = after,
+                    _ => self.undo_stack.push(Command::SetTodo {
+                        label: "edit task",
+                        index,
+                        before,
+                        after,
+                        active_before: self.active_note.active_todo,
+                        active_after: self.active_note.active_todo,
+                    }),
+                }
+            }
+        }
         self.input_mode = InputMode::Normal;
     }
 
-    pub fn edit_char(&mut self, c: char, index: usize) {
-        if self.active_note.todos[index].text.len() < MAX_TODO_LEN {
-            self.active_note.todos[index].text.push(c);
+    /// Apply `f` to the edit buffer of task `index` and write the text back.
+    fn edit(&mut self, index: usize, f: impl FnOnce(&mut LineBuffer)) {
+        let todo = &mut self.active_note.todos[index];
+        if self.editor.text() != todo.text {
+            self.editor = LineBuffer::new(&todo.text, MAX_TODO_LEN);
         }
+        f(&mut self.editor);
+        todo.text = self.editor.text().to_string();
+    }
+
+    pub fn edit_char(&mut self, c: char, index: usize) {
+        self.edit(index, |buf| buf.insert_char(c));
+    }
+
+    /// Insert pasted text at the cursor, cut to the length limit.
+    pub fn edit_str(&mut self, s: &str, index: usize) {
+        self.edit(index, |buf| buf.insert_str(s));
     }
 
     pub fn edit_backspace(&mut self, index: usize) {
-        self.active_note.todos[index].text.pop();
+        self.edit(index, LineBuffer::backspace);
+    }
+
+    pub fn edit_delete(&mut self, index: usize) {
+        self.edit(index, LineBuffer::delete);
+    }
+
+    pub fn edit_delete_word(&mut self, index: usize) {
+        self.edit(index, LineBuffer::delete_word);
+    }
+
+    pub fn edit_delete_to_start(&mut self, index: usize) {
+        self.edit(index, LineBuffer::delete_to_start);
     }
 
     pub fn edit_clear(&mut self, index: usize) {
-        self.active_note.todos[index].text.clear();
+        self.edit(index, LineBuffer::clear);
+    }
+
+    pub fn edit_cursor_left(&mut self, index: usize) {
+        self.edit(index, LineBuffer::move_left);
+    }
+
+    pub fn edit_cursor_right(&mut self, index: usize) {
+        self.edit(index, LineBuffer::move_right);
+    }
+
+    pub fn edit_cursor_home(&mut self, index: usize) {
+        self.edit(index, LineBuffer::move_home);
+    }
+
+    pub fn edit_cursor_end(&mut self, index: usize) {
+        self.edit(index, LineBuffer::move_end);
     }
 
     pub fn toggle_todo(&mut self) {
         let idx = self.selected_todo;
         // Only toggle if todo has text
         if !self.active_note.todos[idx].text.is_empty() {
-            self.active_note.todos[idx].completed = !self.active_note.todos[idx].completed;
-            if self.active_note.todos[idx].completed {
+            let now_ms = self.now_ms();
+            let active_before = self.active_note.active_todo;
+            let todo = &mut self.active_note.todos[idx];
+            let before = todo.clone();
+            todo.completed = !todo.completed;
+            todo.completed_at = todo.completed.then_some(now_ms);
+            let completed = todo.completed;
+            let after = todo.clone();
+            // A finished task stops collecting time
+            if completed && active_before == Some(idx) {
+                self.active_note.active_todo = None;
+            }
+            self.undo_stack.push(Command::SetTodo {
+                label: if completed { "check task" } else { "uncheck task" },
+                index: idx,
+                before,
+                after,
+                active_before,
+                active_after: self.active_note.active_todo,
+            });
+            if completed {
                 self.play_sound();
             }
         }
@@ -142,8 +759,108 @@ impl App {
 
     pub fn remove_todo(&mut self) {
         let idx = self.selected_todo;
-        self.active_note.todos[idx].text.clear();
-        self.active_note.todos[idx].completed = false;
+        let before = std::mem::take(&mut self.active_note.todos[idx]);
+        let active_before = self.active_note.active_todo;
+        if active_before == Some(idx) {
+            self.active_note.active_todo = None;
+        }
+        if !before.text.is_empty() || before.time_spent > 0 {
+            self.undo_stack.push(Command::SetTodo {
+                label: "clear task",
+                index: idx,
+                before,
+                after: Todo::default(),
+                active_before,
+                active_after: self.active_note.active_todo,
+            });
+        }
+    }
+
+    /// Make the selected task the one focused time is credited to, or stop
+    /// tracking it if it already is.
+    pub fn toggle_active_todo(&mut self) {
+        let idx = self.selected_todo;
+        let todo = &self.active_note.todos[idx];
+        if self.active_note.active_todo == Some(idx) {
+            self.active_note.active_todo = None;
+            self.status_message = Some("Stopped tracking task".to_string());
+        } else if !todo.text.is_empty() && !todo.completed {
+            self.status_message = Some(format!("Working on: {}", todo.text));
+            self.active_note.active_todo = Some(idx);
+        }
+    }
+
+    /// Set the task cap, clamped to 1..=`MAX_TODO_CAP`. Empty rows past the
+    /// new cap are dropped; rows with text are kept.
+    pub fn set_todo_cap(&mut self, cap: usize) {
+        self.todo_cap = cap.clamp(1, MAX_TODO_CAP);
+        let todos = &mut self.active_note.todos;
+        while todos.len() > self.todo_cap && todos.last().is_some_and(|t| t.text.is_empty()) {
+            todos.pop();
+        }
+        self.selected_todo = self.selected_todo.min(todos.len() - 1);
+    }
+
+    /// Add an empty task below the selection and start editing it.
+    pub fn add_todo(&mut self) {
+        self.insert_todo_at(self.selected_todo + 1);
+    }
+
+    /// Add an empty task above the selection and start editing it.
+    pub fn insert_todo(&mut self) {
+        self.insert_todo_at(self.selected_todo);
+    }
+
+    fn insert_todo_at(&mut self, index: usize) {
+        if self.active_note.todos.len() >= self.todo_cap {
+            self.status_message = Some(format!("Task list is full ({} max)", self.todo_cap));
+            return;
+        }
+        let index = index.min(self.active_note.todos.len());
+        self.active_note.insert_todo(index, Todo::default());
+        self.undo_stack.push(Command::InsertTodo {
+            index,
+            todo: Todo::default(),
+        });
+        self.selected_todo = index;
+        self.start_editing();
+    }
+
+    /// Remove the selected task row, shifting the ones below it up.
+    /// The last remaining row is cleared instead.
+    pub fn delete_todo(&mut self) {
+        if self.active_note.todos.len() <= 1 {
+            self.remove_todo();
+            return;
+        }
+        let todo = self.active_note.remove_todo(self.selected_todo);
+        self.undo_stack.push(Command::DeleteTodo {
+            index: self.selected_todo,
+            todo,
+        });
+        self.selected_todo = self.selected_todo.min(self.active_note.todos.len() - 1);
+    }
+
+    /// Swap the selected task with the one above it; the selection follows.
+    pub fn move_todo_up(&mut self) {
+        if self.selected_todo == 0 {
+            return;
+        }
+        let (a, b) = (self.selected_todo, self.selected_todo - 1);
+        self.active_note.swap_todos(a, b);
+        self.undo_stack.push(Command::SwapTodos { a, b });
+        self.selected_todo = b;
+    }
+
+    /// Swap the selected task with the one below it; the selection follows.
+    pub fn move_todo_down(&mut self) {
+        if self.selected_todo + 1 >= self.active_note.todos.len() {
+            return;
+        }
+        let (a, b) = (self.selected_todo, self.selected_todo + 1);
+        self.active_note.swap_todos(a, b);
+        self.undo_stack.push(Command::SwapTodos { a, b });
+        self.selected_todo = b;
     }
 
     // Session completion
@@ -158,13 +875,40 @@ impl App {
         self.input_mode = InputMode::Modal;
     }
 
-    fn complete_session(&mut self) {
-        let time_spent = MAX_TIME - self.active_note.time_left;
+    /// Answer the `CompleteSession` modal, choosing whether unfinished tasks
+    /// move into the next note regardless of `carry_over`.
+    pub fn confirm_complete_session(&mut self, carry: bool) {
+        if !matches!(self.modal, Some(ModalKind::CompleteSession)) {
+            return;
+        }
+        self.modal = None;
+        self.input_mode = InputMode::Normal;
+        self.complete_session(carry);
+    }
+
+    fn complete_session(&mut self, carry: bool) {
+        let before = self.session_state();
+        let time_spent = self.active_note.elapsed();
         let completion_number = self.completed_notes.len() as u64 + 1;
+        let finished_at = self.now_ms();
+        let mut runs = self.active_note.runs.clone();
+        if let Some(run) = runs.last_mut().filter(|r| r.ended_at.is_none()) {
+            run.ended_at = Some(finished_at);
+        }
+        let (interruptions, paused_time) = pause_stats(&runs);
 
         let note = CompletedNote {
-            todos: self.active_note.todos.to_vec(),
+            started_at: runs.first().map_or(finished_at, |r| r.started_at),
+            finished_at,
+            utc_offset: local_offset_at(finished_at),
+            todos: self.active_note.todos.clone(),
             time_spent,
+            duration: self.active_note.duration,
+            overtime: self.active_note.overtime,
+            runs,
+            interruptions,
+            paused_time,
+            adjustments: self.active_note.adjustments.clone(),
             completion_number,
         };
 
@@ -172,8 +916,36 @@ impl App {
         self.history_index = Some(self.completed_notes.len() - 1);
 
         // Reset active note
-        self.active_note = ActiveNote::default();
+        self.active_note = self.fresh_note();
         self.selected_todo = 0;
+        if carry {
+            self.carry_unfinished(completion_number);
+        }
+        self.record_session_change("complete session", before);
+    }
+
+    /// Put the unfinished tasks of the note that just completed at the top of
+    /// the new one, each linked back to that session.
+    fn carry_unfinished(&mut self, from: u64) {
+        let Some(finished) = self.completed_notes.last() else {
+            return;
+        };
+        let mut todos: Vec<Todo> = finished
+            .todos
+            .iter()
+            .filter(|t| !t.text.is_empty() && !t.completed)
+            .take(self.todo_cap)
+            .map(|t| Todo {
+                text: t.text.clone(),
+                carried_from: Some(from),
+                ..Todo::default()
+            })
+            .collect();
+        let slots = self.active_note.todos.len();
+        if todos.len() < slots {
+            todos.resize(slots, Todo::default());
+        }
+        self.active_note.todos = todos;
     }
 
     // History navigation
@@ -232,84 +1004,626 @@ impl App {
         if let Some(modal) = self.modal.take() {
             match modal {
                 ModalKind::CompleteSession => {
-                    self.complete_session();
+                    self.complete_session(self.carry_over);
                 }
                 ModalKind::ClearNotes => {
+                    let before = self.session_state();
                     self.completed_notes.clear();
                     self.history_index = None;
+                    self.record_session_change("clear history", before);
                 }
                 ModalKind::NewSession => {
-                    self.active_note = ActiveNote::default();
+                    let before = self.session_state();
+                    self.active_note = self.fresh_note();
+                    self.on_break = None;
+                    self.focus_blocks = 0;
                     self.completed_notes.clear();
                     self.selected_todo = 0;
                     self.history_index = None;
+                    self.record_session_change("new session", before);
                 }
-                ModalKind::Help => {}
+                ModalKind::Help | ModalKind::Stats => {}
+                ModalKind::Away { .. } => self.count_away(),
+                ModalKind::Resume { .. } => self.resume_recovered(),
+                ModalKind::Export => self.export_history(Format::Markdown.exporter()),
             }
         }
         self.input_mode = InputMode::Normal;
     }
 
     pub fn dismiss_modal(&mut self) {
-        self.modal = None;
+        if matches!(self.modal.take(), Some(ModalKind::Resume { .. })) {
+            // Start fresh; the next checkpoint writes over the old note
+            self.recovered = None;
+        }
         self.input_mode = InputMode::Normal;
     }
 
-    // History visibility
+    // Undo
 
-    pub fn toggle_history(&mut self) {
-        self.show_history = !self.show_history;
+    fn session_state(&self) -> SessionState {
+        SessionState {
+            active_note: self.active_note.clone(),
+            completed_notes: self.completed_notes.clone(),
+            history_index: self.history_index,
+            on_break: self.on_break.clone(),
+            focus_blocks: self.focus_blocks,
+            selected_todo: self.selected_todo,
+        }
     }
 
-    // Sound
-
-    fn play_sound(&mut self) {
-        self.sound_pending = true;
+    fn record_session_change(&mut self, label: &'static str, before: SessionState) {
+        let after = self.session_state();
+        self.undo_stack.push(Command::ReplaceSession {
+            label,
+            before: Box::new(before),
+            after: Box::new(after),
+        });
+        self.save_history();
     }
 
-    // Clipboard
+    /// Put back a saved session. A timer that was running then stays paused;
+    /// the time in between is not counted.
+    fn restore_session(&mut self, state: &SessionState, replaced: &SessionState) {
+        let now_ms = self.now_ms();
+        self.active_note = state.active_note.clone();
+        if self.active_note.is_running {
+            self.active_note.is_running = false;
+            self.active_note.target_time = None;
+            self.active_note.end_run(now_ms);
+        }
+        self.on_break = state.on_break.clone();
+        if let Some(b) = self.on_break.as_mut() {
+            b.is_running = false;
+            b.target_time = None;
+        }
+        // Only undo what the change did to the history, keeping notes other
+        // processes saved since
+        let merged = merge_notes(
+            &replaced.completed_notes,
+            &self.completed_notes,
+            &state.completed_notes,
+        );
+        let shown = state.history_index.and_then(|i| state.completed_notes.get(i));
+        self.adopt_notes(merged, shown.map(note_key));
+        self.focus_blocks = state.focus_blocks;
+        self.selected_todo = state.selected_todo;
+    }
 
-    pub fn copy_markdown(&mut self) {
-        if self.completed_notes.is_empty() {
-            return;
+    /// Insert (or, with `insert` false, remove) a task row during undo/redo.
+    fn apply_row(&mut self, index: usize, todo: &Todo, insert: bool) {
+        let len = self.active_note.todos.len();
+        if insert && index <= len {
+            self.active_note.insert_todo(index, todo.clone());
+            self.selected_todo = index;
+        } else if !insert && index < len && len > 1 {
+            self.active_note.remove_todo(index);
+            self.selected_todo = index.min(len - 2);
         }
-        let md = self.build_markdown();
-        match arboard::Clipboard::new().and_then(|mut cb| cb.set_text(&md)) {
-            Ok(_) => {
-                self.status_message = Some("Copied to clipboard!".to_string());
+    }
+
+    /// Run `command` forwards (redo) or backwards (undo).
+    fn apply(&mut self, command: &Command, forward: bool) {
+        match command {
+            Command::SetTodo {
+                index,
+                before,
+                after,
+                active_before,
+                active_after,
+                ..
+            } => {
+                if let Some(todo) = self.active_note.todos.get_mut(*index) {
+                    let (from, to) = if forward { (before, after) } else { (after, before) };
+                    // Only put back what the command changed; time credited
+                    // to the task since then stays
+                    if from.text != to.text {
+                        todo.text = to.text.clone();
+                    }
+                    if from.completed != to.completed {
+                        todo.completed = to.completed;
+                        todo.completed_at = to.completed_at;
+                    }
+                    if from.time_spent != to.time_spent {
+                        todo.time_spent = to.time_spent;
+                    }
+                    if from.carried_from != to.carried_from {
+                        todo.carried_from = to.carried_from;
+                    }
+                    // Picking a task isn't undoable, so only put back a
+                    // change this command made
+                    if active_before != active_after {
+                        self.active_note.active_todo =
+                            if forward { *active_after } else { *active_before };
+                    }
+                    self.selected_todo = *index;
+                }
             }
-            Err(e) => {
-                self.status_message = Some(format!("Clipboard error: {e}"));
+            Command::InsertTodo { index, todo } => self.apply_row(*index, todo, forward),
+            Command::DeleteTodo { index, todo } => self.apply_row(*index, todo, !forward),
+            Command::SwapTodos { a, b } => {
+                if (*a).max(*b) < self.active_note.todos.len() {
+                    self.active_note.swap_todos(*a, *b);
+                    self.selected_todo = if forward { *b } else { *a };
+                }
+            }
+            Command::ReplaceSession { before, after, .. } => {
+                if forward {
+                    self.restore_session(after, before);
+                } else {
+                    self.restore_session(before, after);
+                }
+                self.save_history();
             }
         }
     }
 
-    fn build_markdown(&self) -> String {
-        let mut md = String::from("# One Good Hour\n\n");
-        for note in &self.completed_notes {
-            md.push_str(&format!("## Session {}\n", note.completion_number));
-            md.push_str(&format!("Time spent: {}\n\n", format_time(note.time_spent)));
-            for todo in &note.todos {
-                if !todo.text.is_empty() {
-                    let check = if todo.completed { "x" } else { " " };
-                    md.push_str(&format!("- [{}] {}\n", check, todo.text));
-                }
+    pub fn undo(&mut self) {
+        if self.input_mode != InputMode::Normal {
+            return;
+        }
+        match self.undo_stack.undo() {
+            Some(command) => {
+                self.apply(&command, false);
+                self.status_message = Some(format!("Undid {}", command.label()));
             }
-            md.push('\n');
+            None => self.status_message = Some("Nothing to undo".to_string()),
         }
-        md
     }
 
-}
+    pub fn redo(&mut self) {
+        if self.input_mode != InputMode::Normal {
+            return;
+        }
+        match self.undo_stack.redo() {
+            Some(command) => {
+                self.apply(&command, true);
+                self.status_message = Some(format!("Redid {}", command.label()));
+            }
+            None => self.status_message = Some("Nothing to redo".to_string()),
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // Crash recovery
 
-    fn app_with_todo(text: &str) -> App {
-        let mut app = App::new();
-        app.active_note.todos[0].text = text.to_string();
-        app
+    /// Write the session to the recovery file when it changed, and every
+    /// `CHECKPOINT_INTERVAL_MS` while the note runs. A blank note is written
+    /// too, so the file stays this app's for as long as it runs.
+    fn checkpoint(&mut self) {
+        let Some(file) = &self.recovery else {
+            return;
+        };
+        if self.recovered.is_some() {
+            // Don't overwrite the note being offered for resuming
+            return;
+        }
+        let now_ms = self.now_ms();
+        let checkpoint = Checkpoint {
+            on_break: self.on_break.clone(),
+            focus_blocks: self.focus_blocks,
+            settings: Some(SessionSettings {
+                cycle: self.cycle.clone(),
+                allow_overtime: self.allow_overtime,
+                gap_policy: self.gap_policy,
+                milestones: self.milestones.clone(),
+                todo_cap: self.todo_cap,
+                carry_over: self.carry_over,
+            }),
+            ..Checkpoint::new(self.active_note.clone(), now_ms)
+        };
+        let key = checkpoint_key(&checkpoint);
+        let due = match &self.last_checkpoint {
+            Some((last, at)) => {
+                *last != key || (self.active_note.is_running && now_ms - at >= CHECKPOINT_INTERVAL_MS)
+            }
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        match file.save(&checkpoint) {
+            Ok(true) => {}
+            // Another app took the file over since this one started
+            Ok(false) => {
+                self.recovery = None;
+                self.status_message =
+                    Some("Session file taken by another app; recovery is off for this run".to_string());
+            }
+            Err(e) => self.status_message = Some(format!("Could not save session: {e:#}")),
+        }
+        self.last_checkpoint = Some((key, now_ms));
+    }
+
+    fn resume_recovered(&mut self) {
+        if let Some(checkpoint) = self.recovered.take() {
+            self.take_up(checkpoint);
+            self.selected_todo = 0;
+            self.last_checkpoint = None;
+            self.tick();
+        }
+    }
+
+    // Shared sessions
+
+    /// What clients of a session this app owns draw from. The completed
+    /// notes are only included with `history`.
+    pub fn view(&self, history: bool) -> SessionView {
+        SessionView {
+            active_note: self.active_note.clone(),
+            completed_notes: history.then(|| self.completed_notes.clone()),
+            input_mode: self.input_mode.clone(),
+            selected_todo: self.selected_todo,
+            editor: self.editor.clone(),
+            history_index: self.history_index,
+            modal: self.modal.clone(),
+            status_message: self.status_message.clone(),
+            session_length: self.session_length,
+            cycle: self.cycle.clone(),
+            on_break: self.on_break.clone(),
+            focus_blocks: self.focus_blocks,
+            allow_overtime: self.allow_overtime,
+            gap_policy: self.gap_policy,
+            milestones: self.milestones.clone(),
+            todo_cap: self.todo_cap,
+            carry_over: self.carry_over,
+            saving_history: self.is_saving_history(),
+            sound: self.sound_pending,
+            milestone: self.milestone_pending,
+        }
+    }
+
+    /// Show the session `view` was taken from in place of this app's own.
+    /// The history pane, heatmap, templates, copy template and export
+    /// directory stay as they are, as does an export menu opened here, and
+    /// alerts add to the ones not yet handled. A status message set here
+    /// lasts until the session's own changes.
+    pub fn show_view(&mut self, view: SessionView) {
+        self.active_note = view.active_note;
+        if let Some(notes) = view.completed_notes {
+            self.completed_notes = notes;
+        }
+        self.selected_todo = view.selected_todo;
+        self.editor = view.editor;
+        self.history_index = view.history_index;
+        if !matches!(self.modal, Some(ModalKind::Export)) {
+            self.input_mode = view.input_mode;
+            self.modal = view.modal;
+        }
+        if view.status_message != self.mirror_status {
+            self.status_message = view.status_message.clone();
+            self.mirror_status = view.status_message;
+        }
+        self.session_length = view.session_length;
+        self.cycle = view.cycle;
+        self.on_break = view.on_break;
+        self.focus_blocks = view.focus_blocks;
+        self.allow_overtime = view.allow_overtime;
+        self.gap_policy = view.gap_policy;
+        self.milestones = view.milestones;
+        self.todo_cap = view.todo_cap;
+        self.carry_over = view.carry_over;
+        self.mirror_saving = view.saving_history;
+        self.sound_pending |= view.sound;
+        self.milestone_pending = view.milestone.or(self.milestone_pending);
+    }
+
+    // Saved history
+
+    pub fn is_saving_history(&self) -> bool {
+        self.store.is_some() || self.mirror_saving
+    }
+
+    /// Opt in to saving completed notes at the default location.
+    pub fn start_saving_history(&mut self) {
+        if self.store.is_some() {
+            return;
+        }
+        let Some(store) = HistoryStore::open_default() else {
+            self.status_message = Some("No data directory to save history in".to_string());
+            return;
+        };
+        if self.attach_store(store) {
+            self.save_history();
+            if let Some(store) = &self.store {
+                self.status_message = Some(format!("Saving history to {}", store.path().display()));
+            }
+        }
+    }
+
+    /// Put the saved notes in front of the ones from this run (renumbered to
+    /// follow them) and keep saving. A file that can't be read is left alone
+    /// and saving stays off.
+    fn attach_store(&mut self, store: HistoryStore) -> bool {
+        let mut saved = match store.load() {
+            Ok(saved) => saved,
+            Err(e) => {
+                self.status_message = Some(format!("History not loaded, saving is off: {e:#}"));
+                return false;
+            }
+        };
+        let offset = saved.len() as u64;
+        if offset > 0 {
+            let renumber = |todos: &mut Vec<Todo>| {
+                for from in todos.iter_mut().filter_map(|t| t.carried_from.as_mut()) {
+                    *from += offset;
+                }
+            };
+            for note in &mut self.completed_notes {
+                note.completion_number += offset;
+                renumber(&mut note.todos);
+            }
+            renumber(&mut self.active_note.todos);
+        }
+        self.saved_notes = saved.clone();
+        saved.append(&mut self.completed_notes);
+        self.completed_notes = saved;
+        self.history_index = self.completed_notes.len().checked_sub(1);
+        self.store = Some(store);
+        true
+    }
+
+    /// Save the history, picking up what other processes saved meanwhile.
+    fn save_history(&mut self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        match store.save(&self.saved_notes, &self.completed_notes) {
+            Ok(merged) => {
+                self.saved_notes = merged.notes.clone();
+                let shown = self.history_index.and_then(|i| self.completed_notes.get(i));
+                self.adopt_notes(merged, shown.map(note_key));
+            }
+            Err(e) => self.status_message = Some(format!("Could not save history: {e:#}")),
+        }
+    }
+
+    /// Take over a merged history, keeping carried tasks pointing at their
+    /// sessions and the history pane on the note it showed (or the last).
+    fn adopt_notes(&mut self, merged: MergedNotes, shown: Option<String>) {
+        for from in self.active_note.todos.iter_mut().filter_map(|t| t.carried_from.as_mut()) {
+            if let Some(&new) = merged.renumbered.get(from) {
+                *from = new;
+            }
+        }
+        self.completed_notes = merged.notes;
+        self.history_index = shown
+            .and_then(|key| self.completed_notes.iter().position(|n| note_key(n) == key))
+            .or_else(|| self.completed_notes.len().checked_sub(1));
+    }
+
+    // History visibility
+
+    pub fn toggle_history(&mut self) {
+        self.show_history = !self.show_history;
+    }
+
+    pub fn toggle_heatmap(&mut self) {
+        self.show_heatmap = !self.show_heatmap;
+    }
+
+    // Sound
+
+    fn play_sound(&mut self) {
+        self.sound_pending = true;
+    }
+
+    // Clipboard
+
+    /// Copy the history as Markdown, or through `copy_template` if one is chosen.
+    pub fn copy_markdown(&mut self) {
+        if self.completed_notes.is_empty() {
+            return;
+        }
+        let notes = self.completed_notes.clone();
+        self.copy_notes(&notes, "history");
+    }
+
+    /// Copy just the session shown in the history pane, formatted the same
+    /// way as `copy_markdown`.
+    pub fn copy_current_note(&mut self) {
+        let Some(note) = self.current_note().cloned() else {
+            return;
+        };
+        let what = format!("session {}", note.completion_number);
+        self.copy_notes(&[note], &what);
+    }
+
+    /// The completed note the history pane shows.
+    pub fn current_note(&self) -> Option<&CompletedNote> {
+        self.completed_notes.get(self.history_index.unwrap_or(0))
+    }
+
+    /// `notes` in the copy format.
+    fn copy_text(&self, notes: &[CompletedNote]) -> anyhow::Result<String> {
+        match self.copy_template.and_then(|i| self.templates.get(i)) {
+            Some(t) => t.render(notes),
+            None => Ok(export::markdown(notes)),
+        }
+    }
+
+    /// Render `notes` in the copy format and put them on the clipboard.
+    /// `what` names them in the status message.
+    fn copy_notes(&mut self, notes: &[CompletedNote], what: &str) {
+        let text = match self.copy_text(notes) {
+            Ok(text) => text,
+            Err(e) => {
+                self.status_message = Some(format!("Template error: {e:#}"));
+                return;
+            }
+        };
+        let template = self.copy_template.and_then(|i| self.templates.get(i));
+        let what = match template {
+            Some(t) => format!("{what} as {}", t.name()),
+            None => what.to_string(),
+        };
+        match arboard::Clipboard::new().and_then(|mut cb| cb.set_text(&text)) {
+            Ok(_) => {
+                self.status_message = Some(format!("Copied {what} to clipboard!"));
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Clipboard error: {e}"));
+            }
+        }
+    }
+
+    /// Step the copy format through Markdown and each user template.
+    pub fn cycle_copy_template(&mut self) {
+        if self.templates.is_empty() {
+            self.status_message = Some("No templates found".to_string());
+            return;
+        }
+        self.copy_template = match self.copy_template {
+            None => Some(0),
+            Some(i) if i + 1 < self.templates.len() => Some(i + 1),
+            Some(_) => None,
+        };
+        let name = self.copy_template_name().to_string();
+        self.status_message = Some(format!("Copy as: {name}"));
+    }
+
+    /// Name of the copy format: a template name or "Markdown".
+    pub fn copy_template_name(&self) -> &str {
+        self.copy_template
+            .and_then(|i| self.templates.get(i))
+            .map_or(Format::Markdown.label(), |t| t.name())
+    }
+
+    /// Pick the copy template by name, e.g. from the command line.
+    pub fn select_copy_template(&mut self, name: &str) -> bool {
+        self.copy_template = self.templates.iter().position(|t| t.name() == name);
+        self.copy_template.is_some()
+    }
+
+    // Stats
+
+    pub fn show_stats(&mut self) {
+        self.modal = Some(ModalKind::Stats);
+        self.input_mode = InputMode::Modal;
+    }
+
+    /// Stats over the completed notes.
+    pub fn stats(&self) -> Stats {
+        Stats::compute(&self.completed_notes, self.today())
+    }
+
+    /// Today's date in local time.
+    pub fn today(&self) -> NaiveDate {
+        let now = self.now_ms();
+        at_offset(now, local_offset_at(now)).date_naive()
+    }
+
+    // Export
+
+    pub fn show_export_modal(&mut self) {
+        if self.completed_notes.is_empty() {
+            return;
+        }
+        self.modal = Some(ModalKind::Export);
+        self.input_mode = InputMode::Modal;
+    }
+
+    /// Answer the `Export` modal with the format to save in.
+    pub fn confirm_export(&mut self, format: Format) {
+        if !matches!(self.modal, Some(ModalKind::Export)) {
+            return;
+        }
+        self.modal = None;
+        self.input_mode = InputMode::Normal;
+        self.export_history(format.exporter());
+    }
+
+    /// Answer the `Export` modal with the user template at `index`.
+    pub fn confirm_export_template(&mut self, index: usize) {
+        if !matches!(self.modal, Some(ModalKind::Export)) || index >= self.templates.len() {
+            return;
+        }
+        self.modal = None;
+        self.input_mode = InputMode::Normal;
+        let template = self.templates[index].clone();
+        self.export_history(&template);
+    }
+
+    /// Write the history to a dated file in `export_dir`, replacing an
+    /// export from earlier the same day.
+    fn export_history(&mut self, exporter: &dyn Exporter) {
+        let name = export::default_file_name(exporter, self.now_ms());
+        let path = self.export_dir.join(name);
+        self.status_message = Some(
+            match export::write_file(exporter, &self.completed_notes, &path) {
+                Ok(()) => format!("Saved to {}", path.display()),
+                Err(e) => format!("Export error: {e:#}"),
+            },
+        );
+    }
+
+}
+
+/// Bring a checkpoint up to date for resuming. A running session or break
+/// picked up within `GAP_THRESHOLD_MS` keeps its target, as if the app
+/// never stopped; after a longer gap it resumes paused where the checkpoint
+/// left off.
+fn recover(mut checkpoint: Checkpoint, now_ms: i64) -> Checkpoint {
+    let saved_at = checkpoint.saved_at;
+    if now_ms - saved_at <= GAP_THRESHOLD_MS {
+        // The next tick brings the countdowns up to date
+        return checkpoint;
+    }
+    let note = &mut checkpoint.note;
+    if let Some(target) = note.target_time.filter(|_| note.is_running) {
+        let remaining = seconds_until(target, saved_at);
+        note.time_left = remaining.max(0) as u64;
+        note.overtime = if remaining < 0 { remaining.unsigned_abs() } else { 0 };
+    }
+    note.is_running = false;
+    note.target_time = None;
+    note.end_run(saved_at);
+    if let Some(b) = checkpoint.on_break.as_mut() {
+        if let Some(target) = b.target_time.filter(|_| b.is_running) {
+            b.time_left = seconds_until(target, saved_at).max(0) as u64;
+        }
+        b.is_running = false;
+        b.target_time = None;
+    }
+    checkpoint
+}
+
+/// What identifies a change worth checkpointing. The countdown fields of a
+/// running note or break follow from its target, so ticking alone is not a
+/// change.
+fn checkpoint_key(checkpoint: &Checkpoint) -> String {
+    let mut checkpoint = checkpoint.clone();
+    checkpoint.saved_at = 0;
+    checkpoint.pid = 0;
+    let note = &mut checkpoint.note;
+    if note.is_running {
+        note.time_left = 0;
+        note.overtime = 0;
+    }
+    if let Some(b) = checkpoint.on_break.as_mut().filter(|b| b.is_running) {
+        b.time_left = 0;
+    }
+    serde_json::to_string(&checkpoint).unwrap_or_default()
+}
+
+/// `secs` in milliseconds, capped at what a timestamp can hold.
+fn secs_to_ms(secs: u64) -> i64 {
+    i64::try_from(secs.saturating_mul(1000)).unwrap_or(i64::MAX)
+}
+
+/// Whole seconds from `now_ms` until `target_ms`, rounded up.
+fn seconds_until(target_ms: i64, now_ms: i64) -> i64 {
+    ((target_ms - now_ms) as f64 / 1000.0).ceil() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn app_with_todo(text: &str) -> App {
+        let mut app = App::new();
+        app.active_note.todos[0].text = text.to_string();
+        app
     }
 
     // -- Selection movement --
@@ -391,6 +1705,36 @@ mod tests {
         assert_eq!(app.active_note.todos[0].text, "hell");
     }
 
+    #[test]
+    fn edit_char_limit_counts_graphemes() {
+        let mut app = App::new();
+        app.edit_str(&"é".repeat(MAX_TODO_LEN + 5), 0);
+        assert_eq!(app.active_note.todos[0].text.chars().count(), MAX_TODO_LEN);
+    }
+
+    #[test]
+    fn edit_at_cursor_after_moving() {
+        let mut app = app_with_todo("ello");
+        app.start_editing();
+        app.edit_cursor_home(0);
+        app.edit_char('h', 0);
+        app.edit_cursor_end(0);
+        app.edit_str(" world", 0);
+        assert_eq!(app.active_note.todos[0].text, "hello world");
+        app.edit_delete_word(0);
+        assert_eq!(app.active_note.todos[0].text, "hello ");
+    }
+
+    #[test]
+    fn start_editing_puts_cursor_at_end() {
+        let mut app = app_with_todo("abc");
+        app.start_editing();
+        assert_eq!(app.editor.cursor(), 3);
+        app.edit_cursor_left(0);
+        app.edit_delete_to_start(0);
+        assert_eq!(app.active_note.todos[0].text, "c");
+    }
+
     #[test]
     fn edit_backspace_on_empty_is_safe() {
         let mut app = App::new();
@@ -398,6 +1742,122 @@ mod tests {
         assert_eq!(app.active_note.todos[0].text, "");
     }
 
+    // -- Variable task list --
+
+    fn texts(app: &App) -> Vec<&str> {
+        app.active_note.todos.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn add_todo_inserts_below_selection() {
+        let mut app = app_with_todo("first");
+        app.set_todo_cap(6);
+        app.active_note.todos[1].text = "second".to_string();
+        app.add_todo();
+        assert_eq!(texts(&app), vec!["first", "", "second", "", ""]);
+        assert_eq!(app.selected_todo, 1);
+        assert_eq!(app.input_mode, InputMode::Editing(1));
+    }
+
+    #[test]
+    fn insert_todo_inserts_above_selection() {
+        let mut app = app_with_todo("first");
+        app.set_todo_cap(5);
+        app.insert_todo();
+        assert_eq!(texts(&app)[..2], ["", "first"]);
+        assert_eq!(app.selected_todo, 0);
+    }
+
+    #[test]
+    fn add_todo_blocked_at_cap() {
+        let mut app = App::new();
+        app.add_todo();
+        assert_eq!(app.active_note.todos.len(), DEFAULT_TODO_SLOTS);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.status_message.as_deref(), Some("Task list is full (4 max)"));
+    }
+
+    #[test]
+    fn delete_todo_shifts_rows_up() {
+        let mut app = app_with_todo("a");
+        app.active_note.todos[1].text = "b".to_string();
+        app.active_note.todos[2].text = "c".to_string();
+        app.selected_todo = 1;
+        app.delete_todo();
+        assert_eq!(texts(&app), vec!["a", "c", ""]);
+        assert_eq!(app.selected_todo, 1);
+    }
+
+    #[test]
+    fn delete_last_row_clamps_selection() {
+        let mut app = App::new();
+        app.selected_todo = 3;
+        app.delete_todo();
+        assert_eq!(app.active_note.todos.len(), 3);
+        assert_eq!(app.selected_todo, 2);
+    }
+
+    #[test]
+    fn delete_only_row_clears_it() {
+        let mut app = app_with_todo("solo");
+        app.set_todo_cap(1);
+        assert_eq!(app.active_note.todos.len(), 1);
+        app.delete_todo();
+        assert_eq!(texts(&app), vec![""]);
+    }
+
+    #[test]
+    fn move_todo_swaps_and_keeps_selection() {
+        let mut app = app_with_todo("a");
+        app.active_note.todos[1].text = "b".to_string();
+        app.active_note.todos[1].completed = true;
+        app.selected_todo = 1;
+        app.move_todo_up();
+        assert_eq!(texts(&app), vec!["b", "a", "", ""]);
+        assert_eq!(app.selected_todo, 0);
+        assert!(app.active_note.todos[0].completed);
+        app.move_todo_down();
+        app.move_todo_down();
+        assert_eq!(texts(&app), vec!["a", "", "b", ""]);
+        assert_eq!(app.selected_todo, 2);
+    }
+
+    #[test]
+    fn move_todo_stops_at_edges() {
+        let mut app = app_with_todo("top");
+        app.move_todo_up();
+        assert_eq!(app.selected_todo, 0);
+        assert_eq!(texts(&app)[0], "top");
+        app.selected_todo = 3;
+        app.active_note.todos[3].text = "bottom".to_string();
+        app.move_todo_down();
+        assert_eq!(app.selected_todo, 3);
+        assert_eq!(texts(&app)[3], "bottom");
+    }
+
+    #[test]
+    fn set_todo_cap_clamps_and_keeps_text() {
+        let mut app = app_with_todo("keep");
+        app.active_note.todos[3].text = "also keep".to_string();
+        app.selected_todo = 3;
+        app.set_todo_cap(2);
+        assert_eq!(app.active_note.todos.len(), 4); // last row has text
+        app.set_todo_cap(0);
+        assert_eq!(app.todo_cap, 1);
+        app.set_todo_cap(20);
+        assert_eq!(app.todo_cap, MAX_TODO_CAP);
+    }
+
+    #[test]
+    fn small_cap_limits_new_notes() {
+        let mut app = app_with_todo("task");
+        app.set_todo_cap(2);
+        assert_eq!(app.active_note.todos.len(), 2);
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        assert_eq!(app.active_note.todos.len(), 2);
+    }
+
     // -- Toggle todo --
 
     #[test]
@@ -457,161 +1917,1564 @@ mod tests {
         app.active_note.is_running = true;
         app.active_note.target_time = Some(999);
         app.reset_timer();
-        assert_eq!(app.active_note.time_left, MAX_TIME);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION);
         assert!(!app.active_note.is_running);
         assert!(app.active_note.target_time.is_none());
     }
 
-    // -- Session completion --
-
     #[test]
-    fn show_complete_session_modal_requires_content() {
-        let mut app = App::new();
-        app.show_complete_session_modal();
-        assert!(app.modal.is_none()); // all todos empty, no modal
+    fn reset_timer_uses_note_duration() {
+        let mut app = App::with_session_length(25 * 60);
+        app.active_note.time_left = 100;
+        app.reset_timer();
+        assert_eq!(app.active_note.time_left, 25 * 60);
+    }
 
-        app.active_note.todos[1].text = "something".to_string();
-        app.show_complete_session_modal();
-        assert!(matches!(app.modal, Some(ModalKind::CompleteSession)));
-        assert_eq!(app.input_mode, InputMode::Modal);
+    // -- Clock-driven timer --
+
+    const START_MS: i64 = 1_700_000_000_000;
+
+    fn app_with_clock() -> (App, ManualClock) {
+        let clock = ManualClock::new(START_MS);
+        (App::new().with_clock(clock.clone()), clock)
     }
 
     #[test]
-    fn complete_session_via_confirm() {
-        let mut app = app_with_todo("task 1");
-        app.active_note.todos[1].text = "task 2".to_string();
-        app.active_note.todos[1].completed = true;
-        app.active_note.time_left = 1800; // used half the time
-
+    fn completed_note_records_start_and_end() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "write".to_string();
+        clock.advance_secs(30); // idle before starting doesn't count
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.tick();
+        app.toggle_timer();
+        clock.advance_secs(60);
+        app.toggle_timer();
+        clock.advance_secs(300);
+        app.tick();
         app.show_complete_session_modal();
         app.confirm_modal();
 
-        assert_eq!(app.completed_notes.len(), 1);
         let note = &app.completed_notes[0];
-        assert_eq!(note.completion_number, 1);
-        assert_eq!(note.time_spent, 1800);
-        assert_eq!(note.todos[0].text, "task 1");
-        assert!(note.todos[1].completed);
-
-        // Active note should be reset
-        assert_eq!(app.active_note.time_left, MAX_TIME);
-        assert!(app.active_note.todos[0].text.is_empty());
-        assert_eq!(app.selected_todo, 0);
-        assert_eq!(app.history_index, Some(0));
+        assert_eq!(note.started_at, START_MS + 30_000);
+        assert_eq!(note.finished_at, START_MS + 990_000);
+        assert_eq!(note.utc_offset, local_offset_at(note.finished_at));
+        let md = export::markdown(&app.completed_notes);
+        assert!(md.contains(&format!("When: {}\n", note.span().unwrap())));
+        assert!(md.contains("Runs: "));
     }
 
     #[test]
-    fn dismiss_modal_clears_state() {
-        let mut app = app_with_todo("task");
+    fn never_started_note_spans_its_completion() {
+        let (mut app, _clock) = app_with_clock();
+        app.active_note.todos[0].text = "plan".to_string();
         app.show_complete_session_modal();
-        app.dismiss_modal();
-        assert!(app.modal.is_none());
-        assert_eq!(app.input_mode, InputMode::Normal);
-        assert!(app.completed_notes.is_empty()); // session NOT completed
+        app.confirm_modal();
+        let note = &app.completed_notes[0];
+        assert_eq!((note.started_at, note.finished_at), (START_MS, START_MS));
+        assert!(!export::markdown(&app.completed_notes).contains("Runs: "));
     }
 
-    // -- History navigation --
+    #[test]
+    fn tick_counts_down_with_clock() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        assert_eq!(app.active_note.target_time, Some(START_MS + 3_600_000));
+        clock.advance_secs(90);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 90);
+        assert!(app.active_note.is_running);
+    }
+
+    #[test]
+    fn tick_rounds_partial_seconds_up() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance(1);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION);
+        clock.advance(999);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 1);
+        clock.advance(1);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 1);
+    }
+
+    #[test]
+    fn tick_while_paused_does_nothing() {
+        let (mut app, clock) = app_with_clock();
+        clock.advance_secs(600);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION);
+    }
+
+    #[test]
+    fn pause_and_resume_do_not_drift() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(100);
+        app.tick();
+        app.toggle_timer(); // pause
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100);
+
+        // Time passing while paused is not counted
+        clock.advance_secs(500);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100);
+
+        app.toggle_timer(); // resume
+        clock.advance_secs(50);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 150);
+    }
+
+    #[test]
+    fn pausing_mid_second_keeps_rounded_time() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance(10_400);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 10);
+        app.toggle_timer();
+        app.toggle_timer();
+        clock.advance(1_000);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 11);
+    }
+
+    #[test]
+    fn expiry_stops_timer_and_plays_sound() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.time_left = 5;
+        app.toggle_timer();
+        clock.advance_secs(4);
+        app.tick();
+        assert_eq!(app.active_note.time_left, 1);
+        assert!(!app.sound_pending);
+
+        clock.advance_secs(1);
+        app.tick();
+        assert_eq!(app.active_note.time_left, 0);
+        assert!(!app.active_note.is_running);
+        assert!(app.active_note.target_time.is_none());
+        assert!(app.sound_pending);
+    }
+
+    #[test]
+    fn expiry_after_long_gap_clamps_to_zero() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(2 * DEFAULT_DURATION as i64);
+        app.tick();
+        assert_eq!(app.active_note.time_left, 0);
+        assert_eq!(app.active_note.elapsed(), DEFAULT_DURATION);
+    }
+
+    // -- Extend / shorten --
+
+    #[test]
+    fn extend_while_running_moves_target() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.tick();
+        app.extend_session();
+        assert_eq!(app.active_note.duration, DEFAULT_DURATION + 300);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 300);
+        assert_eq!(app.active_note.target_time, Some(START_MS + 3_900_000));
+        clock.advance_secs(1);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 301);
+    }
+
+    #[test]
+    fn shorten_while_paused() {
+        let mut app = App::new();
+        app.shorten_session();
+        assert_eq!(app.active_note.duration, DEFAULT_DURATION - 300);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 300);
+        assert!(app.active_note.target_time.is_none());
+        assert_eq!(app.session_length, DEFAULT_DURATION); // only this session
+    }
+
+    #[test]
+    fn shorten_cannot_remove_spent_time() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.time_left = 120;
+        app.toggle_timer();
+        app.shorten_session();
+        assert_eq!(app.active_note.time_left, 0);
+        assert_eq!(app.active_note.duration, DEFAULT_DURATION - 120);
+        assert_eq!(app.active_note.adjustments[0].seconds, -120);
+        clock.advance(1);
+        app.tick();
+        assert!(!app.active_note.is_running);
+        assert!(app.sound_pending);
+    }
+
+    #[test]
+    fn extend_in_overtime_absorbs_overtime_first() {
+        let mut app = App::new();
+        app.active_note.time_left = 0;
+        app.active_note.overtime = 120;
+        app.extend_session();
+        assert_eq!(app.active_note.overtime, 0);
+        assert_eq!(app.active_note.time_left, 180);
+    }
+
+    #[test]
+    fn adjustments_recorded_on_completed_note() {
+        let (mut app, _clock) = app_with_clock();
+        app.active_note.todos[0].text = "task".to_string();
+        app.extend_session();
+        app.extend_session();
+        app.shorten_session();
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        let note = &app.completed_notes[0];
+        assert_eq!(note.duration, DEFAULT_DURATION + 300);
+        let seconds: Vec<i64> = note.adjustments.iter().map(|a| a.seconds).collect();
+        assert_eq!(seconds, vec![300, 300, -300]);
+        assert_eq!(note.adjustments[0].at, START_MS);
+        assert!(export::markdown(&app.completed_notes).contains("Adjusted: +5:00, +5:00, -5:00"));
+    }
+
+    #[test]
+    fn adjust_ignored_during_break() {
+        let (mut app, clock) = app_with_clock();
+        expire_focus(&mut app, &clock);
+        app.extend_session();
+        assert!(app.active_note.adjustments.is_empty());
+    }
+
+    // -- Milestones --
+
+    #[test]
+    fn halfway_milestone_fires_once() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(1799);
+        app.tick();
+        assert!(app.milestone_pending.is_none());
+        clock.advance_secs(1);
+        app.tick();
+        assert_eq!(app.milestone_pending.take(), Some(Milestone::Halfway));
+        assert_eq!(app.status_message.as_deref(), Some("Halfway there"));
+        clock.advance_secs(1);
+        app.tick();
+        assert!(app.milestone_pending.is_none());
+    }
+
+    #[test]
+    fn remaining_milestone_fires_before_end() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(DEFAULT_DURATION as i64 - 301);
+        app.tick();
+        app.milestone_pending = None;
+        clock.advance_secs(1);
+        app.tick();
+        assert_eq!(app.milestone_pending, Some(Milestone::Remaining(300)));
+        assert!(!app.sound_pending);
+    }
+
+    #[test]
+    fn custom_elapsed_milestone() {
+        let (mut app, clock) = app_with_clock();
+        app.milestones = vec![Milestone::Elapsed(600)];
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.tick();
+        assert_eq!(app.milestone_pending, Some(Milestone::Elapsed(600)));
+    }
+
+    #[test]
+    fn jump_over_several_milestones_reports_latest() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(DEFAULT_DURATION as i64 - 60);
+        app.tick();
+        assert_eq!(app.milestone_pending, Some(Milestone::Remaining(300)));
+    }
+
+    #[test]
+    fn milestones_refire_after_reset() {
+        let (mut app, clock) = app_with_clock();
+        app.milestones = vec![Milestone::Elapsed(10)];
+        app.toggle_timer();
+        clock.advance_secs(10);
+        app.tick();
+        assert!(app.milestone_pending.take().is_some());
+        app.reset_timer();
+        app.toggle_timer();
+        clock.advance_secs(10);
+        app.tick();
+        assert!(app.milestone_pending.is_some());
+    }
+
+    // -- Suspend / clock jumps --
+
+    fn running_app_with_clock(policy: GapPolicy) -> (App, ManualClock) {
+        let (mut app, clock) = app_with_clock();
+        app.gap_policy = policy;
+        app.tick();
+        app.toggle_timer();
+        clock.advance_secs(100);
+        app.tick();
+        (app, clock)
+    }
+
+    #[test]
+    fn backward_clock_change_does_not_corrupt_countdown() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        clock.jump_wall(-2 * 3_600_000);
+        clock.advance_secs(10);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 110);
+        assert!(app.active_note.is_running);
+    }
+
+    #[test]
+    fn small_forward_clock_change_is_absorbed() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Pause);
+        clock.jump_wall(30_000);
+        clock.advance_secs(1);
+        app.tick();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 101);
+        assert!(app.active_note.is_running);
+    }
+
+    #[test]
+    fn suspend_with_pause_policy_stops_at_last_tick() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Pause);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        assert!(!app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100);
+        assert_eq!(app.active_note.runs[0].ended_at, Some(START_MS + 100_000));
+        assert!(app.modal.is_none());
+    }
+
+    #[test]
+    fn suspend_with_count_policy_counts_gap() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Count);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        assert!(app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100 - 2400);
+    }
+
+    #[test]
+    fn suspend_with_ask_policy_opens_modal() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        assert!(!app.active_note.is_running);
+        assert!(matches!(app.modal, Some(ModalKind::Away { seconds: 2400 })));
+        assert_eq!(app.input_mode, InputMode::Modal);
+    }
+
+    #[test]
+    fn confirming_away_counts_gap_and_resumes() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        app.confirm_modal();
+        assert!(app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100 - 2400);
+        assert_eq!(app.active_note.runs.len(), 1);
+        assert!(app.active_note.runs[0].ended_at.is_none());
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn time_on_the_away_prompt_counts_in_runs_and_countdown() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        clock.advance_secs(30);
+        app.tick();
+        app.confirm_modal();
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100 - 2400 - 30);
+        clock.advance_secs(10);
+        app.tick();
+        app.toggle_timer();
+        let run = &app.active_note.runs[0];
+        let run_secs = (run.ended_at.unwrap() - run.started_at) as u64 / 1000;
+        assert_eq!(run_secs, app.active_note.elapsed());
+    }
+
+    #[test]
+    fn away_prompt_closes_the_edit_first() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        app.start_editing();
+        app.edit_str("typed", 0);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        assert!(matches!(app.modal, Some(ModalKind::Away { .. })));
+        app.dismiss_modal();
+        assert_eq!(app.active_note.todos[0].text, "typed");
+        app.undo();
+        assert_eq!(app.active_note.todos[0].text, "");
+    }
+
+    #[test]
+    fn away_prompt_leaves_an_open_modal() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        app.active_note.todos[0].text = "task".to_string();
+        app.show_complete_session_modal();
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        assert!(matches!(app.modal, Some(ModalKind::CompleteSession)));
+        assert!(!app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100);
+    }
+
+    #[test]
+    fn dismissing_away_keeps_session_paused() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        app.dismiss_modal();
+        assert!(!app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 100);
+    }
+
+    #[test]
+    fn counted_gap_past_zero_expires_session() {
+        let (mut app, clock) = running_app_with_clock(GapPolicy::Ask);
+        clock.jump_wall(2 * 3_600_000);
+        app.tick();
+        app.confirm_modal();
+        assert_eq!(app.active_note.time_left, 0);
+        assert_eq!(app.phase(), Phase::ShortBreak);
+    }
+
+    // -- Run/pause intervals --
+
+    #[test]
+    fn toggling_records_run_intervals() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        clock.advance_secs(60);
+        app.toggle_timer();
+        clock.advance_secs(30);
+        app.toggle_timer();
+        assert_eq!(
+            app.active_note.runs,
+            vec![
+                RunInterval { started_at: START_MS, ended_at: Some(START_MS + 60_000) },
+                RunInterval { started_at: START_MS + 90_000, ended_at: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn expiry_closes_run_at_target_time() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.time_left = 10;
+        app.toggle_timer();
+        clock.advance_secs(25);
+        app.tick();
+        assert_eq!(app.active_note.runs[0].ended_at, Some(START_MS + 10_000));
+    }
+
+    #[test]
+    fn completed_note_reports_interruptions() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "task".to_string();
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.toggle_timer(); // pause 1
+        clock.advance_secs(120);
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.toggle_timer(); // pause 2
+        clock.advance_secs(130);
+        app.toggle_timer();
+        clock.advance_secs(60);
+        app.tick();
+
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        let note = &app.completed_notes[0];
+        assert_eq!(note.interruptions, 2);
+        assert_eq!(note.paused_time, 250);
+        assert_eq!(note.runs.len(), 3);
+        assert_eq!(note.runs[2].ended_at, Some(START_MS + 1_510_000));
+        assert!(export::markdown(&app.completed_notes).contains("Interruptions: 2 (4:10 paused)"));
+    }
+
+    #[test]
+    fn reset_timer_clears_runs() {
+        let (mut app, _clock) = app_with_clock();
+        app.toggle_timer();
+        app.reset_timer();
+        assert!(app.active_note.runs.is_empty());
+    }
+
+    // -- Overtime --
+
+    #[test]
+    fn overtime_keeps_counting_past_zero() {
+        let (mut app, clock) = app_with_clock();
+        app.allow_overtime = true;
+        app.active_note.time_left = 10;
+        app.toggle_timer();
+        clock.advance_secs(10);
+        app.tick();
+        assert!(app.active_note.is_running);
+        assert!(app.sound_pending);
+        assert_eq!(app.phase(), Phase::Focus); // no automatic break
+
+        app.sound_pending = false;
+        clock.advance_secs(754);
+        app.tick();
+        assert_eq!(app.active_note.time_left, 0);
+        assert_eq!(app.active_note.overtime, 754);
+        assert_eq!(app.timer_display(), "+12:34");
+        assert!(!app.sound_pending); // sound only when crossing zero
+    }
+
+    #[test]
+    fn overtime_resumes_after_pause() {
+        let (mut app, clock) = app_with_clock();
+        app.allow_overtime = true;
+        app.active_note.time_left = 0;
+        app.active_note.overtime = 60;
+        app.toggle_timer();
+        assert!(app.active_note.is_running);
+        clock.advance_secs(30);
+        app.tick();
+        assert_eq!(app.active_note.overtime, 90);
+    }
+
+    #[test]
+    fn overtime_disabled_blocks_start_at_zero() {
+        let mut app = App::new();
+        app.active_note.time_left = 0;
+        app.toggle_timer();
+        assert!(!app.active_note.is_running);
+    }
+
+    #[test]
+    fn overtime_recorded_separately_on_completion() {
+        let mut app = app_with_todo("deep work");
+        app.active_note.time_left = 0;
+        app.active_note.overtime = 300;
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        let note = &app.completed_notes[0];
+        assert_eq!(note.time_spent, DEFAULT_DURATION);
+        assert_eq!(note.overtime, 300);
+        assert!(export::markdown(&app.completed_notes).contains("(+5:00 overtime)"));
+    }
+
+    #[test]
+    fn reset_timer_clears_overtime() {
+        let mut app = App::new();
+        app.active_note.time_left = 0;
+        app.active_note.overtime = 300;
+        app.reset_timer();
+        assert_eq!(app.active_note.overtime, 0);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION);
+    }
+
+    #[test]
+    fn longer_session_length_absorbs_overtime() {
+        let mut app = App::with_session_length(25 * 60);
+        app.active_note.time_left = 0;
+        app.active_note.overtime = 5 * 60;
+        app.set_session_length(45 * 60);
+        assert_eq!(app.active_note.overtime, 0);
+        assert_eq!(app.active_note.time_left, 15 * 60);
+    }
+
+    // -- Work/break cycle --
+
+    fn expire_focus(app: &mut App, clock: &ManualClock) {
+        app.toggle_timer();
+        clock.advance_secs(app.active_note.time_left as i64);
+        app.tick();
+    }
+
+    #[test]
+    fn focus_expiry_starts_short_break() {
+        let (mut app, clock) = app_with_clock();
+        expire_focus(&mut app, &clock);
+        assert_eq!(app.phase(), Phase::ShortBreak);
+        assert_eq!(app.focus_blocks, 1);
+        assert!(app.sound_pending);
+        assert!(!app.is_timer_running()); // auto-start off by default
+        assert_eq!(app.phase_time_left(), app.cycle.short_break);
+    }
+
+    #[test]
+    fn long_break_after_configured_blocks() {
+        let (mut app, clock) = app_with_clock();
+        app.cycle.long_break_every = 2;
+        expire_focus(&mut app, &clock);
+        assert_eq!(app.phase(), Phase::ShortBreak);
+        app.skip_break();
+        app.reset_timer();
+        expire_focus(&mut app, &clock);
+        assert_eq!(app.phase(), Phase::LongBreak);
+        assert_eq!(app.phase_time_left(), app.cycle.long_break);
+    }
+
+    #[test]
+    fn auto_start_runs_break_and_next_focus() {
+        let (mut app, clock) = app_with_clock();
+        app.cycle.auto_start = true;
+        expire_focus(&mut app, &clock);
+        assert!(app.is_timer_running());
+
+        // Finish the session during the break so the next focus has time left
+        app.active_note.todos[0].text = "done".to_string();
+        app.show_complete_session_modal();
+        app.confirm_modal();
+
+        clock.advance_secs(app.cycle.short_break as i64);
+        app.tick();
+        assert_eq!(app.phase(), Phase::Focus);
+        assert!(app.active_note.is_running);
+    }
+
+    #[test]
+    fn break_time_does_not_count_toward_time_spent() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "task".to_string();
+        app.set_session_length(600);
+        expire_focus(&mut app, &clock);
+        app.toggle_timer(); // start the break
+        clock.advance_secs(120);
+        app.tick();
+        assert_eq!(app.phase_time_left(), app.cycle.short_break - 120);
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        assert_eq!(app.completed_notes[0].time_spent, 600);
+    }
+
+    #[test]
+    fn toggle_timer_during_break_leaves_focus_alone() {
+        let (mut app, clock) = app_with_clock();
+        expire_focus(&mut app, &clock);
+        app.toggle_timer();
+        assert!(app.on_break.as_ref().unwrap().is_running);
+        assert!(!app.active_note.is_running);
+        app.toggle_timer();
+        assert!(!app.on_break.as_ref().unwrap().is_running);
+    }
+
+    #[test]
+    fn skip_break_returns_to_focus() {
+        let (mut app, clock) = app_with_clock();
+        expire_focus(&mut app, &clock);
+        app.skip_break();
+        assert_eq!(app.phase(), Phase::Focus);
+        assert!(app.on_break.is_none());
+    }
+
+    #[test]
+    fn zero_length_break_is_skipped() {
+        let (mut app, clock) = app_with_clock();
+        app.cycle.short_break = 0;
+        expire_focus(&mut app, &clock);
+        assert_eq!(app.phase(), Phase::Focus);
+        assert_eq!(app.focus_blocks, 1);
+    }
+
+    // -- Session length --
+
+    #[test]
+    fn with_session_length_sets_active_note() {
+        let app = App::with_session_length(45 * 60);
+        assert_eq!(app.session_length, 45 * 60);
+        assert_eq!(app.active_note.duration, 45 * 60);
+        assert_eq!(app.active_note.time_left, 45 * 60);
+    }
+
+    #[test]
+    fn set_session_length_keeps_elapsed_time() {
+        let mut app = App::new();
+        app.active_note.time_left = DEFAULT_DURATION - 600; // 10 min spent
+        app.set_session_length(25 * 60);
+        assert_eq!(app.active_note.duration, 25 * 60);
+        assert_eq!(app.active_note.time_left, 15 * 60);
+        assert_eq!(app.session_length, 25 * 60);
+    }
+
+    #[test]
+    fn set_session_length_blocked_while_running() {
+        let mut app = App::new();
+        app.toggle_timer();
+        app.set_session_length(25 * 60);
+        assert_eq!(app.active_note.duration, DEFAULT_DURATION);
+    }
+
+    #[test]
+    fn huge_session_length_does_not_overflow() {
+        let (mut app, clock) = app_with_clock();
+        app.set_session_length(1 << 62);
+        app.toggle_timer();
+        clock.advance_secs(1);
+        app.tick();
+        assert!(app.active_note.is_running);
+        assert!(app.active_note.time_left > 0);
+    }
+
+    #[test]
+    fn cycle_session_length_wraps() {
+        let mut app = App::new();
+        app.cycle_session_length();
+        assert_eq!(app.active_note.duration, 90 * 60);
+        app.cycle_session_length();
+        assert_eq!(app.active_note.duration, 25 * 60);
+        app.cycle_session_length();
+        assert_eq!(app.active_note.duration, 45 * 60);
+    }
+
+    // -- Session completion --
+
+    #[test]
+    fn show_complete_session_modal_requires_content() {
+        let mut app = App::new();
+        app.show_complete_session_modal();
+        assert!(app.modal.is_none()); // all todos empty, no modal
+
+        app.active_note.todos[1].text = "something".to_string();
+        app.show_complete_session_modal();
+        assert!(matches!(app.modal, Some(ModalKind::CompleteSession)));
+        assert_eq!(app.input_mode, InputMode::Modal);
+    }
+
+    #[test]
+    fn complete_session_via_confirm() {
+        let mut app = app_with_todo("task 1");
+        app.active_note.todos[1].text = "task 2".to_string();
+        app.active_note.todos[1].completed = true;
+        app.active_note.time_left = 1800; // used half the time
+
+        app.show_complete_session_modal();
+        app.confirm_modal();
+
+        assert_eq!(app.completed_notes.len(), 1);
+        let note = &app.completed_notes[0];
+        assert_eq!(note.completion_number, 1);
+        assert_eq!(note.time_spent, 1800);
+        assert_eq!(note.duration, DEFAULT_DURATION);
+        assert_eq!(note.todos[0].text, "task 1");
+        assert!(note.todos[1].completed);
+
+        // Active note should be reset
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION);
+        assert!(app.active_note.todos[0].text.is_empty());
+        assert_eq!(app.selected_todo, 0);
+        assert_eq!(app.history_index, Some(0));
+    }
+
+    #[test]
+    fn dismiss_modal_clears_state() {
+        let mut app = app_with_todo("task");
+        app.show_complete_session_modal();
+        app.dismiss_modal();
+        assert!(app.modal.is_none());
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.completed_notes.is_empty()); // session NOT completed
+    }
+
+    // -- History navigation --
+
+    fn app_with_history(count: usize) -> App {
+        let mut app = App::new();
+        for i in 0..count {
+            app.active_note.todos[0].text = format!("session {}", i + 1);
+            app.show_complete_session_modal();
+            app.confirm_modal();
+        }
+        app
+    }
+
+    #[test]
+    fn history_navigation_empty() {
+        let mut app = App::new();
+        app.next_history();
+        assert_eq!(app.history_index, None);
+        app.prev_history();
+        assert_eq!(app.history_index, None);
+    }
+
+    #[test]
+    fn next_history_from_none_starts_at_zero() {
+        let mut app = app_with_history(3);
+        app.history_index = None;
+        app.next_history();
+        assert_eq!(app.history_index, Some(0));
+    }
+
+    #[test]
+    fn prev_history_from_none_starts_at_last() {
+        let mut app = app_with_history(3);
+        app.history_index = None;
+        app.prev_history();
+        assert_eq!(app.history_index, Some(2));
+    }
+
+    #[test]
+    fn next_history_clamps_at_end() {
+        let mut app = app_with_history(2);
+        app.history_index = Some(1);
+        app.next_history();
+        assert_eq!(app.history_index, Some(1)); // stays at last
+    }
+
+    #[test]
+    fn prev_history_clamps_at_start() {
+        let mut app = app_with_history(2);
+        app.history_index = Some(0);
+        app.prev_history();
+        assert_eq!(app.history_index, Some(0)); // stays at first
+    }
+
+    #[test]
+    fn history_navigation_walks_through() {
+        let mut app = app_with_history(3);
+        app.history_index = Some(0);
+        app.next_history();
+        assert_eq!(app.history_index, Some(1));
+        app.next_history();
+        assert_eq!(app.history_index, Some(2));
+        app.prev_history();
+        assert_eq!(app.history_index, Some(1));
+    }
+
+    #[test]
+    fn complete_session_records_planned_duration() {
+        let mut app = App::with_session_length(25 * 60);
+        app.active_note.todos[0].text = "short block".to_string();
+        app.active_note.time_left = 5 * 60;
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        let note = &app.completed_notes[0];
+        assert_eq!(note.time_spent, 20 * 60);
+        assert_eq!(note.duration, 25 * 60);
+        assert_eq!(app.active_note.duration, 25 * 60);
+    }
+
+    // -- Clear notes --
+
+    #[test]
+    fn clear_notes_modal_requires_history() {
+        let mut app = App::new();
+        app.show_clear_notes_modal();
+        assert!(app.modal.is_none());
+    }
+
+    #[test]
+    fn clear_notes_via_confirm() {
+        let mut app = app_with_history(2);
+        assert_eq!(app.completed_notes.len(), 2);
+        app.show_clear_notes_modal();
+        app.confirm_modal();
+        assert!(app.completed_notes.is_empty());
+        assert_eq!(app.history_index, None);
+    }
+
+    // -- Build markdown --
+
+    #[test]
+    fn build_markdown_format() {
+        let mut app = app_with_todo("write tests");
+        app.active_note.todos[0].completed = true;
+        app.active_note.todos[1].text = "review PR".to_string();
+        app.active_note.time_left = 2400; // 20 min spent
+
+        app.show_complete_session_modal();
+        app.confirm_modal();
+
+        let md = export::markdown(&app.completed_notes);
+        assert!(md.contains("# One Good Hour"));
+        assert!(md.contains("## Session 1"));
+        assert!(md.contains("Time spent: 20:00 of 60:00"));
+        assert!(md.contains("- [x] write tests"));
+        assert!(md.contains("- [ ] review PR"));
+    }
+
+    // -- Stats --
+
+    #[test]
+    fn stats_cover_completed_notes() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "focus".to_string();
+        app.active_note.todos[1].text = "rest".to_string();
+        app.toggle_timer();
+        clock.advance_secs(3600);
+        app.tick();
+        app.active_note.todos[0].completed = true;
+        app.show_complete_session_modal();
+        app.confirm_modal();
+
+        let stats = app.stats();
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(stats.focused, 3600);
+        assert_eq!(stats.completion_rate(), Some(0.5));
+        assert_eq!(stats.current_streak, 1);
+        app.show_stats();
+        assert!(matches!(app.modal, Some(ModalKind::Stats)));
+    }
+
+    // -- Export --
+
+    #[test]
+    fn export_writes_chosen_format() {
+        let dir = std::env::temp_dir().join(format!("1gh-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut app = App::new();
+        app.export_dir = dir.clone();
+        app.show_export_modal(); // nothing to export yet
+        assert!(app.modal.is_none());
+
+        complete(&mut app, "ship it");
+        app.confirm_export(Format::Csv); // no modal open: ignored
+        assert!(app.status_message.is_none());
+        app.show_export_modal();
+        app.confirm_export(Format::Csv);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let path = dir.join(export::default_file_name(&export::Csv, app.now_ms()));
+        let csv = std::fs::read_to_string(&path).unwrap();
+        assert!(csv.contains(",ship it,false,"));
+        assert_eq!(app.status_message, Some(format!("Saved to {}", path.display())));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copy_template_cycles_through_templates() {
+        let mut app = App::new();
+        app.cycle_copy_template();
+        assert_eq!(app.status_message.as_deref(), Some("No templates found"));
+        app.templates = vec![
+            Template::parse("journal", "md", "").unwrap(),
+            Template::parse("standup", "txt", "").unwrap(),
+        ];
+        app.cycle_copy_template();
+        assert_eq!(app.copy_template_name(), "journal");
+        app.cycle_copy_template();
+        assert_eq!(app.status_message.as_deref(), Some("Copy as: standup"));
+        app.cycle_copy_template();
+        assert_eq!(app.copy_template_name(), "Markdown");
+        assert!(app.select_copy_template("standup"));
+        assert!(!app.select_copy_template("nope"));
+        assert_eq!(app.copy_template, None);
+    }
+
+    #[test]
+    fn export_with_template() {
+        let dir = std::env::temp_dir().join(format!("1gh-export-tmpl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut app = App::new();
+        app.export_dir = dir.clone();
+        app.templates = vec![Template::parse(
+            "standup",
+            "txt",
+            "{{#sessions}}{{#todos}}* {{text}}\n{{/todos}}{{/sessions}}",
+        )
+        .unwrap()];
+        complete(&mut app, "ship it");
+        app.show_export_modal();
+        app.confirm_export_template(3); // no such template
+        assert!(app.modal.is_some());
+        app.confirm_export_template(0);
+        let path = dir.join(export::default_file_name(&app.templates[0], app.now_ms()));
+        assert!(path.file_name().unwrap().to_str().unwrap().starts_with("standup-"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "* ship it\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copy_text_of_current_note_only() {
+        let mut app = App::new();
+        assert!(app.current_note().is_none());
+        complete(&mut app, "first");
+        complete(&mut app, "second");
+        app.prev_history();
+        let note = app.current_note().unwrap().clone();
+        assert_eq!(note.completion_number, 1);
+        let text = app.copy_text(&[note]).unwrap();
+        assert!(text.contains("## Session 1"));
+        assert!(!text.contains("second"));
+
+        app.templates = vec![Template::parse("t", "txt", "{{#sessions}}{{number}}{{/sessions}}").unwrap()];
+        app.copy_template = Some(0);
+        assert_eq!(app.copy_text(&app.completed_notes).unwrap(), "12");
+    }
 
-    fn app_with_history(count: usize) -> App {
+    // -- Task time tracking --
+
+    #[test]
+    fn tick_credits_active_todo() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "a".to_string();
+        app.active_note.todos[1].text = "b".to_string();
+        app.toggle_active_todo();
+        assert_eq!(app.active_note.active_todo, Some(0));
+        app.toggle_timer();
+        clock.advance_secs(90);
+        app.tick();
+        app.selected_todo = 1;
+        app.toggle_active_todo();
+        clock.advance_secs(30);
+        app.tick();
+        assert_eq!(app.active_note.todos[0].time_spent, 90);
+        assert_eq!(app.active_note.todos[1].time_spent, 30);
+    }
+
+    #[test]
+    fn paused_time_is_not_credited() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "a".to_string();
+        app.toggle_active_todo();
+        app.tick();
+        clock.advance_secs(60);
+        app.tick();
+        assert_eq!(app.active_note.todos[0].time_spent, 0);
+    }
+
+    #[test]
+    fn active_todo_needs_open_task_with_text() {
         let mut app = App::new();
-        for i in 0..count {
-            app.active_note.todos[0].text = format!("session {}", i + 1);
-            app.show_complete_session_modal();
-            app.confirm_modal();
-        }
+        app.toggle_active_todo();
+        assert_eq!(app.active_note.active_todo, None);
+        app.active_note.todos[0].text = "a".to_string();
+        app.active_note.todos[0].completed = true;
+        app.toggle_active_todo();
+        assert_eq!(app.active_note.active_todo, None);
+    }
+
+    #[test]
+    fn completing_task_stamps_time_and_stops_tracking() {
+        let (mut app, _clock) = app_with_clock();
+        app.active_note.todos[0].text = "a".to_string();
+        app.toggle_active_todo();
+        app.toggle_todo();
+        assert_eq!(app.active_note.todos[0].completed_at, Some(START_MS));
+        assert_eq!(app.active_note.active_todo, None);
+        app.toggle_todo();
+        assert_eq!(app.active_note.todos[0].completed_at, None);
+    }
+
+    #[test]
+    fn task_times_reach_history_and_markdown() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "write tests".to_string();
+        app.toggle_active_todo();
+        app.toggle_timer();
+        clock.advance_secs(125);
+        app.tick();
+        app.toggle_todo();
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        let todo = &app.completed_notes[0].todos[0];
+        assert_eq!(todo.time_spent, 125);
+        assert_eq!(todo.completed_at, Some(START_MS + 125_000));
+        assert!(export::markdown(&app.completed_notes).contains("- [x] write tests (2:05)"));
+    }
+
+    // -- Carry over --
+
+    fn app_with_mixed_todos() -> App {
+        let mut app = app_with_todo("done");
+        app.active_note.todos[0].completed = true;
+        app.active_note.todos[2].text = "open".to_string();
+        app.active_note.todos[3].text = "also open".to_string();
         app
     }
 
     #[test]
-    fn history_navigation_empty() {
-        let mut app = App::new();
-        app.next_history();
-        assert_eq!(app.history_index, None);
-        app.prev_history();
-        assert_eq!(app.history_index, None);
+    fn complete_without_carry_starts_empty() {
+        let mut app = app_with_mixed_todos();
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        assert_eq!(texts(&app), vec!["", "", "", ""]);
     }
 
     #[test]
-    fn next_history_from_none_starts_at_zero() {
-        let mut app = app_with_history(3);
-        app.history_index = None;
-        app.next_history();
+    fn carry_over_option_seeds_unfinished() {
+        let mut app = app_with_mixed_todos();
+        app.carry_over = true;
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        assert_eq!(texts(&app), vec!["open", "also open", "", ""]);
+        assert!(app.active_note.todos[..2].iter().all(|t| t.carried_from == Some(1)));
+        assert_eq!(app.active_note.todos[2].carried_from, None);
+        // The finished note keeps its own copy
+        assert_eq!(app.completed_notes[0].todos[2].text, "open");
+    }
+
+    #[test]
+    fn modal_choice_overrides_option() {
+        let mut app = app_with_mixed_todos();
+        app.show_complete_session_modal();
+        app.confirm_complete_session(true);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(texts(&app)[0], "open");
+
+        app.carry_over = true;
+        app.show_complete_session_modal();
+        app.confirm_complete_session(false);
+        assert_eq!(texts(&app), vec!["", "", "", ""]);
+        assert_eq!(app.completed_notes.len(), 2);
+    }
+
+    #[test]
+    fn confirm_complete_session_needs_modal() {
+        let mut app = app_with_mixed_todos();
+        app.confirm_complete_session(true);
+        assert!(app.completed_notes.is_empty());
+    }
+
+    #[test]
+    fn carried_todos_are_marked_in_markdown() {
+        let mut app = app_with_mixed_todos();
+        app.show_complete_session_modal();
+        app.confirm_complete_session(true);
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        assert!(export::markdown(&app.completed_notes).contains("- [ ] open (from session 1)"));
+    }
+
+    // -- Undo --
+
+    #[test]
+    fn undo_restores_cleared_task_and_redo_clears_again() {
+        let mut app = app_with_todo("keep me");
+        app.remove_todo();
+        assert_eq!(app.active_note.todos[0].text, "");
+        app.undo();
+        assert_eq!(app.active_note.todos[0].text, "keep me");
+        assert_eq!(app.status_message.as_deref(), Some("Undid clear task"));
+        app.redo();
+        assert_eq!(app.active_note.todos[0].text, "");
+    }
+
+    #[test]
+    fn undo_check_restores_active_task() {
+        let mut app = app_with_todo("focus");
+        app.toggle_active_todo();
+        app.toggle_todo();
+        assert_eq!(app.active_note.active_todo, None);
+        app.undo();
+        assert!(!app.active_note.todos[0].completed);
+        assert_eq!(app.active_note.active_todo, Some(0));
+        app.redo();
+        assert!(app.active_note.todos[0].completed);
+        assert_eq!(app.active_note.active_todo, None);
+    }
+
+    #[test]
+    fn undo_and_redo_keep_time_spent_since() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "draft".to_string();
+        app.toggle_active_todo();
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.tick();
+        app.start_editing();
+        app.edit_str(" two", 0);
+        clock.advance_secs(60);
+        app.tick();
+        app.stop_editing();
+        clock.advance_secs(60);
+        app.tick();
+        app.undo();
+        assert_eq!(app.active_note.todos[0].text, "draft");
+        app.redo();
+        assert_eq!(app.active_note.todos[0].text, "draft two");
+        assert_eq!(app.active_note.todos[0].time_spent, 720);
+
+        app.toggle_todo();
+        app.undo();
+        assert!(!app.active_note.todos[0].completed);
+        assert_eq!(app.active_note.todos[0].completed_at, None);
+        assert_eq!(app.active_note.todos[0].time_spent, 720);
+    }
+
+    #[test]
+    fn edit_session_is_one_undo_step() {
+        let mut app = app_with_todo("draft");
+        app.start_editing();
+        app.edit_clear(0);
+        app.edit_str("final", 0);
+        app.stop_editing();
+        app.undo();
+        assert_eq!(app.active_note.todos[0].text, "draft");
+        assert!(!app.undo_stack.can_undo());
+    }
+
+    #[test]
+    fn added_task_and_its_text_undo_together() {
+        let mut app = app_with_todo("first");
+        app.set_todo_cap(6);
+        app.add_todo();
+        app.edit_str("second", 1);
+        app.stop_editing();
+        assert_eq!(texts(&app), vec!["first", "second", "", "", ""]);
+        app.undo();
+        assert_eq!(texts(&app), vec!["first", "", "", ""]);
+        app.redo();
+        assert_eq!(texts(&app)[1], "second");
+    }
+
+    #[test]
+    fn undo_check_delete_and_move() {
+        let mut app = app_with_todo("a");
+        app.active_note.todos[1].text = "b".to_string();
+        app.toggle_todo();
+        app.move_todo_down();
+        app.delete_todo();
+        assert_eq!(texts(&app), vec!["b", "", ""]);
+        app.undo();
+        assert_eq!(texts(&app), vec!["b", "a", "", ""]);
+        assert_eq!(app.selected_todo, 1);
+        app.undo();
+        assert_eq!(texts(&app), vec!["a", "b", "", ""]);
+        assert_eq!(app.selected_todo, 0);
+        app.undo();
+        assert!(!app.active_note.todos[0].completed);
+    }
+
+    #[test]
+    fn undo_clear_history() {
+        let mut app = app_with_todo("task");
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        app.show_clear_notes_modal();
+        app.confirm_modal();
+        assert!(app.completed_notes.is_empty());
+        app.undo();
+        assert_eq!(app.completed_notes.len(), 1);
         assert_eq!(app.history_index, Some(0));
     }
 
     #[test]
-    fn prev_history_from_none_starts_at_last() {
-        let mut app = app_with_history(3);
-        app.history_index = None;
-        app.prev_history();
-        assert_eq!(app.history_index, Some(2));
+    fn undo_complete_session_restores_paused_note() {
+        let (mut app, clock) = app_with_clock();
+        app.active_note.todos[0].text = "task".to_string();
+        app.toggle_timer();
+        clock.advance_secs(60);
+        app.tick();
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        clock.advance_secs(600);
+        app.undo();
+        assert!(app.completed_notes.is_empty());
+        assert_eq!(app.active_note.todos[0].text, "task");
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 60);
+        assert!(!app.active_note.is_running);
+        app.redo();
+        assert_eq!(app.completed_notes.len(), 1);
     }
 
     #[test]
-    fn next_history_clamps_at_end() {
-        let mut app = app_with_history(2);
-        app.history_index = Some(1);
-        app.next_history();
-        assert_eq!(app.history_index, Some(1)); // stays at last
+    fn undo_new_session() {
+        let mut app = app_with_todo("task");
+        app.show_complete_session_modal();
+        app.confirm_modal();
+        app.active_note.todos[0].text = "next".to_string();
+        app.show_new_session_modal();
+        app.confirm_modal();
+        app.undo();
+        assert_eq!(app.completed_notes.len(), 1);
+        assert_eq!(app.active_note.todos[0].text, "next");
     }
 
     #[test]
-    fn prev_history_clamps_at_start() {
-        let mut app = app_with_history(2);
-        app.history_index = Some(0);
-        app.prev_history();
-        assert_eq!(app.history_index, Some(0)); // stays at first
+    fn ticks_do_not_fill_undo_stack() {
+        let (mut app, clock) = app_with_clock();
+        app.toggle_timer();
+        for _ in 0..100 {
+            clock.advance_secs(1);
+            app.tick();
+        }
+        assert!(!app.undo_stack.can_undo());
+        app.undo();
+        assert_eq!(app.status_message.as_deref(), Some("Nothing to undo"));
     }
 
     #[test]
-    fn history_navigation_walks_through() {
-        let mut app = app_with_history(3);
-        app.history_index = Some(0);
-        app.next_history();
-        assert_eq!(app.history_index, Some(1));
-        app.next_history();
-        assert_eq!(app.history_index, Some(2));
-        app.prev_history();
+    fn new_change_drops_redo() {
+        let mut app = app_with_todo("a");
+        app.toggle_todo();
+        app.undo();
+        app.remove_todo();
+        assert!(!app.undo_stack.can_redo());
+    }
+
+    // -- Saved history --
+
+    fn temp_store(name: &str) -> HistoryStore {
+        let dir = std::env::temp_dir().join(format!("1gh-app-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        HistoryStore::new(dir.join("history.json"))
+    }
+
+    fn complete(app: &mut App, text: &str) {
+        app.active_note.todos[0].text = text.to_string();
+        app.show_complete_session_modal();
+        app.confirm_modal();
+    }
+
+    #[test]
+    fn completed_notes_survive_restart() {
+        let store = temp_store("restart");
+        let mut app = App::new().with_store(store.clone());
+        assert!(app.is_saving_history());
+        complete(&mut app, "first");
+        complete(&mut app, "second");
+
+        let app = App::new().with_store(store);
+        assert_eq!(app.completed_notes.len(), 2);
+        assert_eq!(app.completed_notes[1].todos[0].text, "second");
         assert_eq!(app.history_index, Some(1));
     }
 
-    // -- Clear notes --
+    #[test]
+    fn apps_sharing_a_store_keep_each_others_notes() {
+        let store = temp_store("shared");
+        let tui_clock = ManualClock::new(START_MS);
+        let desktop_clock = ManualClock::new(START_MS + 1_000);
+        let mut tui = App::new().with_clock(tui_clock.clone()).with_store(store.clone());
+        let mut desktop = App::new().with_clock(desktop_clock).with_store(store.clone());
+        complete(&mut tui, "from the terminal");
+        complete(&mut desktop, "from the desktop");
+        assert_eq!(store.load().unwrap().len(), 2);
+        assert_eq!(desktop.completed_notes.len(), 2);
+        assert_eq!(desktop.history_index, Some(1));
+
+        tui_clock.advance_secs(60);
+        complete(&mut tui, "again");
+        let texts: Vec<String> =
+            store.load().unwrap().iter().map(|n| n.todos[0].text.clone()).collect();
+        assert_eq!(texts, ["from the terminal", "from the desktop", "again"]);
+
+        // Undoing a completion only takes back that one
+        tui.undo();
+        assert_eq!(store.load().unwrap().len(), 2);
+        assert_eq!(tui.completed_notes.len(), 2);
+    }
 
     #[test]
-    fn clear_notes_modal_requires_history() {
-        let mut app = App::new();
+    fn clear_and_undo_are_saved() {
+        let store = temp_store("clear");
+        let mut app = App::new().with_store(store.clone());
+        complete(&mut app, "task");
         app.show_clear_notes_modal();
-        assert!(app.modal.is_none());
+        app.confirm_modal();
+        assert!(store.load().unwrap().is_empty());
+        app.undo();
+        assert_eq!(store.load().unwrap().len(), 1);
     }
 
     #[test]
-    fn clear_notes_via_confirm() {
-        let mut app = app_with_history(2);
+    fn unreadable_store_is_not_overwritten() {
+        let store = temp_store("corrupt");
+        std::fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        std::fs::write(store.path(), "garbage").unwrap();
+        let mut app = App::new().with_store(store.clone());
+        assert!(!app.is_saving_history());
+        assert!(app.status_message.as_deref().unwrap().starts_with("History not loaded"));
+        complete(&mut app, "task");
+        assert_eq!(std::fs::read_to_string(store.path()).unwrap(), "garbage");
+    }
+
+    #[test]
+    fn attaching_later_renumbers_this_runs_notes() {
+        let store = temp_store("attach");
+        let mut earlier = App::new().with_store(store.clone());
+        complete(&mut earlier, "saved");
+
+        let mut app = App::new();
+        complete(&mut app, "unsaved");
+        assert!(app.attach_store(store));
         assert_eq!(app.completed_notes.len(), 2);
-        app.show_clear_notes_modal();
+        assert_eq!(app.completed_notes[1].completion_number, 2);
+    }
+
+    // -- Crash recovery --
+
+    fn temp_recovery(name: &str) -> RecoveryFile {
+        let dir = std::env::temp_dir().join(format!("1gh-recovery-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        RecoveryFile::new(dir.join("session.json"))
+    }
+
+    /// A run that typed a task and ran the timer for 10 minutes.
+    fn crashed_run(file: &RecoveryFile, clock: &ManualClock, running: bool) {
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file.clone());
+        app.active_note.todos[0].text = "survive".to_string();
+        app.toggle_timer();
+        clock.advance_secs(600);
+        app.tick();
+        if !running {
+            app.toggle_timer();
+            app.tick();
+        }
+    }
+
+    #[test]
+    fn blank_note_is_checkpointed_but_not_offered() {
+        let file = temp_recovery("blank");
+        let mut app = App::new().with_recovery(file.clone());
+        app.tick();
+        // Written all the same, to keep the file this app's
+        assert!(file.load().unwrap().unwrap().note.is_blank());
+        let app = App::new().with_recovery(file);
+        assert!(app.modal.is_none());
+    }
+
+    #[test]
+    fn edits_are_checkpointed() {
+        let file = temp_recovery("edits");
+        let mut app = App::new().with_recovery(file.clone());
+        app.active_note.todos[0].text = "typed".to_string();
+        app.tick();
+        let checkpoint = file.load().unwrap().unwrap();
+        assert_eq!(checkpoint.note.todos[0].text, "typed");
+    }
+
+    #[test]
+    fn quick_restart_keeps_running() {
+        let file = temp_recovery("quick");
+        let clock = ManualClock::new(START_MS);
+        crashed_run(&file, &clock, true);
+        clock.advance_secs(20);
+
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file);
+        assert!(matches!(app.modal, Some(ModalKind::Resume { tasks: 1, .. })));
         app.confirm_modal();
-        assert!(app.completed_notes.is_empty());
-        assert_eq!(app.history_index, None);
+        assert!(app.active_note.is_running);
+        assert_eq!(app.active_note.todos[0].text, "survive");
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 620);
     }
 
-    // -- Build markdown --
+    #[test]
+    fn long_gap_resumes_paused_at_checkpoint() {
+        let file = temp_recovery("long");
+        let clock = ManualClock::new(START_MS);
+        crashed_run(&file, &clock, true);
+        clock.advance_secs(5); // worked a little past the last checkpoint
+        clock.advance_secs(3600);
+
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file);
+        assert!(matches!(
+            app.modal,
+            Some(ModalKind::Resume { time_left, .. }) if time_left == DEFAULT_DURATION - 600
+        ));
+        app.confirm_modal();
+        assert!(!app.active_note.is_running);
+        assert_eq!(app.active_note.target_time, None);
+        assert_eq!(app.active_note.runs[0].ended_at, Some(START_MS + 600_000));
+        app.toggle_timer();
+        assert_eq!(
+            app.active_note.target_time,
+            Some(clock.now_ms() + (DEFAULT_DURATION as i64 - 600) * 1000)
+        );
+    }
 
     #[test]
-    fn build_markdown_format() {
-        let mut app = app_with_todo("write tests");
-        app.active_note.todos[0].completed = true;
-        app.active_note.todos[1].text = "review PR".to_string();
-        app.active_note.time_left = 2400; // 20 min spent
+    fn crash_mid_break_resumes_the_break() {
+        let file = temp_recovery("break");
+        let clock = ManualClock::new(START_MS);
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file.clone());
+        app.cycle.short_break = 120;
+        app.active_note.todos[0].text = "survive".to_string();
+        app.toggle_timer();
+        clock.advance_secs(DEFAULT_DURATION as i64);
+        app.tick();
+        app.toggle_timer();
+        clock.advance_secs(30);
+        app.tick();
+        drop(app);
+        clock.advance_secs(3600);
 
-        app.show_complete_session_modal();
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file);
         app.confirm_modal();
+        let on_break = app.on_break.as_ref().unwrap();
+        assert_eq!(on_break.time_left, 90);
+        assert!(!on_break.is_running);
+        assert_eq!(app.focus_blocks, 1);
+        assert_eq!(app.cycle.short_break, 120);
+        assert_eq!(app.active_note.todos[0].text, "survive");
+    }
 
-        let md = app.build_markdown();
-        assert!(md.contains("# One Good Hour"));
-        assert!(md.contains("## Session 1"));
-        assert!(md.contains("Time spent: 20:00"));
-        assert!(md.contains("- [x] write tests"));
-        assert!(md.contains("- [ ] review PR"));
+    #[test]
+    fn paused_note_resumes_paused() {
+        let file = temp_recovery("paused");
+        let clock = ManualClock::new(START_MS);
+        crashed_run(&file, &clock, false);
+        clock.advance_secs(30);
+
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file);
+        app.confirm_modal();
+        assert!(!app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 600);
+    }
+
+    #[test]
+    fn declining_resume_discards_checkpoint() {
+        let file = temp_recovery("decline");
+        let clock = ManualClock::new(START_MS);
+        crashed_run(&file, &clock, false);
+
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file.clone());
+        app.tick();
+        assert!(file.load().unwrap().is_some()); // kept while the modal is open
+        app.dismiss_modal();
+        app.tick();
+        assert!(file.load().unwrap().unwrap().note.is_blank());
+        assert_eq!(app.active_note.todos[0].text, "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn running_apps_session_is_not_offered_or_overwritten() {
+        let file = temp_recovery("live");
+        let clock = ManualClock::new(START_MS);
+        crashed_run(&file, &clock, true);
+        // As if written by a process that is still running
+        let mut checkpoint = file.load().unwrap().unwrap();
+        checkpoint.pid = 1;
+        std::fs::write(file.path(), serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file.clone());
+        assert!(app.modal.is_none());
+        assert!(app.status_message.as_deref().unwrap().contains("in use by process 1"));
+        assert!(app.status_message.as_deref().unwrap().contains("recovery is off"));
+        app.tick();
+        app.active_note.todos[0].text = "mine".to_string();
+        app.tick();
+        let kept = file.load().unwrap().unwrap();
+        assert_eq!(kept.pid, 1);
+        assert_eq!(kept.note.todos[0].text, "survive");
+    }
+
+    #[test]
+    fn file_taken_over_later_turns_recovery_off() {
+        let file = temp_recovery("taken");
+        let clock = ManualClock::new(START_MS);
+        let mut app = App::new().with_clock(clock.clone()).with_recovery(file.clone());
+        app.tick();
+        // Another app claims the file; any pid but ours will do
+        let mut checkpoint = file.load().unwrap().unwrap();
+        checkpoint.pid = 0;
+        checkpoint.note.todos[0].text = "theirs".to_string();
+        std::fs::write(file.path(), serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+
+        app.active_note.todos[0].text = "mine".to_string();
+        app.tick();
+        assert!(app.status_message.as_deref().unwrap().contains("recovery is off"));
+        assert!(app.recovery.is_none());
+        assert_eq!(file.load().unwrap().unwrap().note.todos[0].text, "theirs");
     }
 
     // -- Toggle history --
@@ -631,4 +3494,84 @@ mod tests {
         app.toggle_history();
         assert!(app.show_history);
     }
+
+    #[test]
+    fn session_carries_over_between_runs() {
+        let file = temp_recovery("session");
+        let clock = ManualClock::new(1_000_000);
+        let mut app = App::new().with_clock(clock.clone()).with_session(file.clone()).unwrap();
+        app.active_note.todos[0].text = "task".to_string();
+        app.toggle_timer();
+        app.tick();
+        drop(app);
+
+        // The next command finds the timer still running, ten minutes on
+        clock.advance_secs(600);
+        let app = App::new().with_clock(clock.clone()).with_session(file.clone()).unwrap();
+        assert!(app.modal.is_none());
+        assert!(app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, DEFAULT_DURATION - 600);
+        assert_eq!(app.active_note.todos[0].text, "task");
+    }
+
+    #[test]
+    fn session_keeps_break_and_settings_between_runs() {
+        let file = temp_recovery("session-break");
+        let clock = ManualClock::new(1_000_000);
+        let mut app = App::new().with_clock(clock.clone()).with_session(file.clone()).unwrap();
+        app.cycle.short_break = 120;
+        app.carry_over = true;
+        app.active_note.todos[0].text = "task".to_string();
+        app.toggle_timer();
+        clock.advance_secs(DEFAULT_DURATION as i64);
+        app.tick();
+        assert!(app.on_break.is_some());
+        drop(app);
+
+        let app = App::new().with_clock(clock.clone()).with_session(file).unwrap();
+        assert_eq!(app.on_break.as_ref().map(|b| b.duration), Some(120));
+        assert_eq!(app.focus_blocks, 1);
+        assert_eq!(app.cycle.short_break, 120);
+        assert!(app.carry_over);
+    }
+
+    #[test]
+    fn peeking_at_a_session_moves_nothing_on() {
+        let file = temp_recovery("peek");
+        let clock = ManualClock::new(1_000_000);
+        let mut app = App::new().with_clock(clock.clone()).with_session(file.clone()).unwrap();
+        app.active_note.todos[0].text = "task".to_string();
+        app.toggle_timer();
+        app.tick();
+        drop(app);
+        let saved = std::fs::read_to_string(file.path()).unwrap();
+
+        // Past the end of the session: a tick would start the break
+        clock.advance_secs(DEFAULT_DURATION as i64 + 60);
+        let app = App::new().with_clock(clock.clone()).peek_session(&file).unwrap();
+        assert!(app.on_break.is_none());
+        assert!(app.active_note.is_running);
+        assert_eq!(app.active_note.time_left, 0);
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), saved);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn session_held_by_a_running_app_is_refused() {
+        let file = temp_recovery("session-held");
+        let mut checkpoint = Checkpoint::new(ActiveNote::new(DEFAULT_DURATION), 0);
+        checkpoint.pid = 1;
+        std::fs::create_dir_all(file.path().parent().unwrap()).unwrap();
+        std::fs::write(file.path(), serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+        let error = App::new().with_session(file).err().unwrap();
+        assert!(error.to_string().contains("open in another app"));
+    }
+
+    #[test]
+    fn toggle_heatmap_flips() {
+        let mut app = App::new();
+        assert!(!app.show_heatmap);
+        app.toggle_heatmap();
+        assert!(app.show_heatmap);
+    }
 }
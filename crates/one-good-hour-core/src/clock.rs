@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+/// Wall-clock time that passes between two ticks without the monotonic clock
+/// following it counts as a gap (the machine slept or the clock jumped ahead).
+pub const GAP_THRESHOLD_MS: i64 = 60_000;
+
+/// Source of the current time for the timer.
+pub trait Clock: Send {
+    /// Wall-clock time in milliseconds since the Unix epoch.
+    fn now_ms(&self) -> i64;
+
+    /// Milliseconds on a clock that never jumps and does not advance while
+    /// the system is suspended. Only differences between readings matter.
+    fn monotonic_ms(&self) -> i64;
+}
+
+/// What to do with a running focus session after a gap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GapPolicy {
+    /// Stop the session at the last tick before the gap.
+    Pause,
+    /// Treat the gap as focused time.
+    Count,
+    /// Pause, then ask whether to count the gap.
+    #[default]
+    Ask,
+}
+
+impl GapPolicy {
+    pub fn label(self) -> &'static str {
+        match self {
+            GapPolicy::Pause => "pause",
+            GapPolicy::Count => "count",
+            GapPolicy::Ask => "ask",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GapPolicy::Pause => GapPolicy::Count,
+            GapPolicy::Count => GapPolicy::Ask,
+            GapPolicy::Ask => GapPolicy::Pause,
+        }
+    }
+}
+
+/// The real clock, backed by `chrono::Utc::now()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+
+    fn monotonic_ms(&self) -> i64 {
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed().as_millis() as i64
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, so a
+/// test can keep one handle and give another to `App`.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<AtomicI64>,
+    monotonic: Arc<AtomicI64>,
+}
+
+impl ManualClock {
+    pub fn new(start_ms: i64) -> Self {
+        Self {
+            now: Arc::new(AtomicI64::new(start_ms)),
+            monotonic: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    /// Set the wall clock without moving the monotonic clock.
+    pub fn set(&self, ms: i64) {
+        self.now.store(ms, Ordering::SeqCst);
+    }
+
+    /// Let time pass normally: both clocks move.
+    pub fn advance(&self, ms: i64) {
+        self.now.fetch_add(ms, Ordering::SeqCst);
+        self.monotonic.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    /// Move only the wall clock, as a suspend or a clock change would.
+    pub fn jump_wall(&self, ms: i64) {
+        self.now.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    pub fn advance_secs(&self, secs: i64) {
+        self.advance(secs * 1000);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> i64 {
+        self.now.load(Ordering::SeqCst)
+    }
+
+    fn monotonic_ms(&self) -> i64 {
+        self.monotonic.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_clones_share_time() {
+        let clock = ManualClock::new(1_000);
+        let handle = clock.clone();
+        handle.advance_secs(2);
+        assert_eq!(clock.now_ms(), 3_000);
+        handle.set(0);
+        assert_eq!(clock.now_ms(), 0);
+        assert_eq!(clock.monotonic_ms(), 2_000);
+    }
+
+    #[test]
+    fn jump_wall_leaves_monotonic_alone() {
+        let clock = ManualClock::new(0);
+        clock.jump_wall(40 * 60_000);
+        assert_eq!(clock.now_ms(), 40 * 60_000);
+        assert_eq!(clock.monotonic_ms(), 0);
+    }
+
+    #[test]
+    fn gap_policy_cycles() {
+        assert_eq!(GapPolicy::default(), GapPolicy::Ask);
+        assert_eq!(GapPolicy::Ask.next(), GapPolicy::Pause);
+        assert_eq!(GapPolicy::Pause.next().next(), GapPolicy::Ask);
+    }
+}
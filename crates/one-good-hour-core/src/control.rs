@@ -0,0 +1,446 @@
+//! Driving a session one step at a time, for scripts and frontends without
+//! an event loop: apply an [`Action`], then read back the [`Status`].
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, InputMode, ModalKind};
+use crate::clock::GapPolicy;
+use crate::cycle::{Break, CycleConfig, Phase};
+use crate::editor::LineBuffer;
+use crate::milestone::Milestone;
+use crate::types::{format_overtime, format_time, ActiveNote, CompletedNote};
+
+/// Longest session length an action may set, in seconds; the same bound as
+/// `1gh start --duration`.
+const MAX_SESSION_LENGTH: u64 = 24 * 60 * 60;
+
+/// A change to the session. Tasks are numbered from 1, as shown in
+/// [`Status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Start or resume the timer, first changing the session length (in
+    /// seconds) if one is given.
+    Start {
+        #[serde(default)]
+        session_length: Option<u64>,
+    },
+    Pause,
+    Reset,
+    /// Fill the first empty task row, or add a row below the others.
+    Add { text: String },
+    /// Check off a task.
+    Check { number: usize },
+    /// Complete the session. `carry` moves unfinished tasks into the next
+    /// one; `None` goes by the app's carry-over setting.
+    Complete { carry: Option<bool> },
+}
+
+/// Apply `action` to `app`, returning a line describing what happened.
+/// Actions that can't be carried out are errors; ones with nothing to do
+/// (pausing a paused timer) just say so. Nothing is applied while the app
+/// is editing a task or has a prompt open, as an attached screen's user is
+/// in the middle of something.
+pub fn apply(app: &mut App, action: &Action) -> Result<String> {
+    if app.input_mode != InputMode::Normal || app.modal.is_some() {
+        bail!("the session is busy in the TUI (editing a task or answering a prompt); try again once it's done");
+    }
+    match action {
+        Action::Start { session_length } => {
+            if let Some(seconds) = *session_length {
+                if !(60..=MAX_SESSION_LENGTH).contains(&seconds) {
+                    bail!("session length must be between 1 and 1440 minutes");
+                }
+                if app.is_timer_running() {
+                    bail!("the timer is running; pause it to change the session length");
+                }
+                app.set_session_length(seconds);
+                // The start message shows the new length
+                app.status_message = None;
+            }
+            if app.is_timer_running() {
+                return Ok(format!("Timer already running: {}", app.timer_display()));
+            }
+            app.toggle_timer();
+            if !app.is_timer_running() {
+                bail!("no time left; reset the timer or turn on overtime");
+            }
+            Ok(format!("Timer started: {}", app.timer_display()))
+        }
+        Action::Pause => {
+            if !app.is_timer_running() {
+                return Ok(format!("Timer already paused: {}", app.timer_display()));
+            }
+            app.toggle_timer();
+            Ok(format!("Timer paused: {}", app.timer_display()))
+        }
+        Action::Reset => {
+            app.reset_timer();
+            Ok(format!("Timer reset: {}", app.timer_display()))
+        }
+        Action::Add { text } => add_task(app, text.trim()),
+        Action::Check { number } => {
+            let index = task_index(app, *number)?;
+            let todo = &app.active_note.todos[index];
+            if todo.completed {
+                return Ok(format!("Already done: {}", todo.text));
+            }
+            app.selected_todo = index;
+            app.toggle_todo();
+            Ok(format!("Checked off: {}", app.active_note.todos[index].text))
+        }
+        Action::Complete { carry } => {
+            app.show_complete_session_modal();
+            if !matches!(app.modal, Some(ModalKind::CompleteSession)) {
+                bail!("nothing to complete; add a task first");
+            }
+            app.confirm_complete_session(carry.unwrap_or(app.carry_over));
+            Ok(format!("Completed session {}", app.completed_notes.len()))
+        }
+    }
+}
+
+fn add_task(app: &mut App, text: &str) -> Result<String> {
+    if text.is_empty() {
+        bail!("task text is empty");
+    }
+    match app.active_note.todos.iter().position(|t| t.text.is_empty()) {
+        Some(index) => {
+            app.selected_todo = index;
+            app.start_editing();
+        }
+        None => {
+            app.selected_todo = app.active_note.todos.len() - 1;
+            app.add_todo();
+        }
+    }
+    let InputMode::Editing(index) = app.input_mode else {
+        bail!("task list is full ({} max)", app.todo_cap);
+    };
+    app.edit_str(text, index);
+    app.stop_editing();
+    Ok(format!("Added task {}: {}", index + 1, app.active_note.todos[index].text))
+}
+
+/// The row of task `number`, which must have text.
+fn task_index(app: &App, number: usize) -> Result<usize> {
+    match number.checked_sub(1) {
+        Some(index) if app.active_note.todos.get(index).is_some_and(|t| !t.text.is_empty()) => {
+            Ok(index)
+        }
+        _ => bail!("no task {number}"),
+    }
+}
+
+/// Where the session stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    pub phase: Phase,
+    pub running: bool,
+    /// Seconds left in the phase.
+    pub time_left: u64,
+    /// Seconds counted past the planned duration.
+    pub overtime: u64,
+    /// Planned length of the focus session, in seconds.
+    pub duration: u64,
+    /// Tasks with text.
+    pub tasks: Vec<TaskStatus>,
+    /// Sessions in the history.
+    pub completed_sessions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub number: usize,
+    pub text: String,
+    pub completed: bool,
+    /// Focused time is being credited to this task.
+    pub active: bool,
+    /// Focused seconds spent on it.
+    pub time_spent: u64,
+}
+
+impl Status {
+    pub fn of(app: &App) -> Self {
+        let note = &app.active_note;
+        Status {
+            phase: app.phase(),
+            running: app.is_timer_running(),
+            time_left: app.phase_time_left(),
+            overtime: note.overtime,
+            duration: note.duration,
+            tasks: note
+                .todos
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| !t.text.is_empty())
+                .map(|(i, t)| TaskStatus {
+                    number: i + 1,
+                    text: t.text.clone(),
+                    completed: t.completed,
+                    active: note.active_todo == Some(i),
+                    time_spent: t.time_spent,
+                })
+                .collect(),
+            completed_sessions: app.completed_notes.len(),
+        }
+    }
+}
+
+/// "Focus 42:13 left, running" followed by the tasks, one per line.
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = if self.overtime > 0 && !self.phase.is_break() {
+            format!("{} over", format_overtime(self.overtime))
+        } else {
+            format!("{} left", format_time(self.time_left))
+        };
+        let state = if self.running { "running" } else { "paused" };
+        write!(f, "{} {time}, {state}", self.phase.label())?;
+        for task in &self.tasks {
+            let check = if task.completed { 'x' } else { ' ' };
+            write!(f, "\n{}. [{check}] {}", task.number, task.text)?;
+            if task.active {
+                write!(f, " (working on)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a client draws a session another process owns from: the note,
+/// history and settings, and that app's selection, edit and modal too, so
+/// every attached screen shows the same thing. See [`App::view`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionView {
+    pub active_note: ActiveNote,
+    /// Left out when the history hasn't changed since the last view sent.
+    pub completed_notes: Option<Vec<CompletedNote>>,
+    pub input_mode: InputMode,
+    pub selected_todo: usize,
+    pub editor: LineBuffer,
+    pub history_index: Option<usize>,
+    pub modal: Option<ModalKind>,
+    pub status_message: Option<String>,
+    pub session_length: u64,
+    pub cycle: CycleConfig,
+    pub on_break: Option<Break>,
+    pub focus_blocks: u32,
+    pub allow_overtime: bool,
+    pub gap_policy: GapPolicy,
+    pub milestones: Vec<Milestone>,
+    pub todo_cap: usize,
+    pub carry_over: bool,
+    pub saving_history: bool,
+    /// The timer went off since the last view.
+    pub sound: bool,
+    /// The milestone that fired since the last view.
+    pub milestone: Option<Milestone>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn add(app: &mut App, text: &str) -> Result<String> {
+        apply(app, &Action::Add { text: text.to_string() })
+    }
+
+    #[test]
+    fn add_fills_empty_rows_then_appends() {
+        let mut app = App::new();
+        app.set_todo_cap(4);
+        for i in 0..4 {
+            add(&mut app, &format!("task {i}")).unwrap();
+        }
+        assert_eq!(app.active_note.todos.len(), 4);
+        assert_eq!(app.active_note.todos[3].text, "task 3");
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert!(add(&mut app, "one more").unwrap_err().to_string().contains("full"));
+        assert!(add(&mut app, "  ").is_err());
+        // Adding is undoable like any other edit
+        app.undo();
+        assert_eq!(app.active_note.todos.iter().filter(|t| !t.text.is_empty()).count(), 3);
+    }
+
+    #[test]
+    fn check_goes_by_task_number() {
+        let mut app = App::new();
+        add(&mut app, "first").unwrap();
+        add(&mut app, "second").unwrap();
+        assert_eq!(apply(&mut app, &Action::Check { number: 2 }).unwrap(), "Checked off: second");
+        assert!(app.active_note.todos[1].completed);
+        assert!(apply(&mut app, &Action::Check { number: 2 }).unwrap().starts_with("Already"));
+        assert!(apply(&mut app, &Action::Check { number: 0 }).is_err());
+        assert!(apply(&mut app, &Action::Check { number: 3 }).is_err());
+    }
+
+    #[test]
+    fn start_pause_and_reset() {
+        let clock = ManualClock::new(1_000_000);
+        let mut app = App::new().with_clock(clock.clone());
+        let start = Action::Start { session_length: None };
+        apply(&mut app, &start).unwrap();
+        assert!(app.is_timer_running());
+        assert!(apply(&mut app, &start).unwrap().starts_with("Timer already running"));
+        let shorter = Action::Start { session_length: Some(25 * 60) };
+        assert!(apply(&mut app, &shorter).is_err());
+        clock.advance(90_000);
+        app.tick();
+        assert_eq!(apply(&mut app, &Action::Pause).unwrap(), "Timer paused: 58:30");
+        assert!(apply(&mut app, &Action::Pause).unwrap().starts_with("Timer already paused"));
+        assert_eq!(apply(&mut app, &Action::Reset).unwrap(), "Timer reset: 60:00");
+        assert_eq!(apply(&mut app, &shorter).unwrap(), "Timer started: 25:00");
+    }
+
+    #[test]
+    fn complete_needs_a_task() {
+        let mut app = App::new();
+        let complete = Action::Complete { carry: Some(true) };
+        assert!(apply(&mut app, &complete).is_err());
+        add(&mut app, "done").unwrap();
+        add(&mut app, "not yet").unwrap();
+        apply(&mut app, &Action::Check { number: 1 }).unwrap();
+        assert_eq!(apply(&mut app, &complete).unwrap(), "Completed session 1");
+        assert_eq!(app.active_note.todos[0].text, "not yet");
+        assert!(app.modal.is_none());
+    }
+
+    #[test]
+    fn status_lists_tasks_with_text() {
+        let mut app = App::new();
+        add(&mut app, "write").unwrap();
+        add(&mut app, "review").unwrap();
+        apply(&mut app, &Action::Check { number: 1 }).unwrap();
+        app.selected_todo = 1;
+        app.toggle_active_todo();
+        let status = Status::of(&app);
+        assert_eq!(status.tasks.len(), 2);
+        assert_eq!(
+            status.to_string(),
+            "Focus 60:00 left, paused\n1. [x] write\n2. [ ] review (working on)"
+        );
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+    }
+
+    #[test]
+    fn view_mirrors_the_session() {
+        let mut owner = App::new();
+        add(&mut owner, "shared").unwrap();
+        apply(&mut owner, &Action::Complete { carry: None }).unwrap();
+        add(&mut owner, "next").unwrap();
+        owner.selected_todo = 0;
+        owner.start_editing();
+
+        let json = serde_json::to_string(&owner.view(true)).unwrap();
+        let mut mirror = App::new();
+        mirror.show_history = false;
+        mirror.show_view(serde_json::from_str(&json).unwrap());
+        assert_eq!(mirror.active_note.todos[0].text, "next");
+        assert_eq!(mirror.completed_notes.len(), 1);
+        assert!(matches!(mirror.input_mode, InputMode::Editing(0)));
+        assert_eq!(mirror.editor.text(), "next");
+        // The mirror's own layout stays
+        assert!(!mirror.show_history);
+
+        // A view without history keeps the notes already shown
+        let mut view = owner.view(false);
+        view.saving_history = true;
+        mirror.show_view(view);
+        assert_eq!(mirror.completed_notes.len(), 1);
+        assert!(mirror.is_saving_history());
+    }
+
+    #[test]
+    fn view_leaves_the_mirrors_own_export_and_messages() {
+        let mut owner = App::new();
+        add(&mut owner, "shared").unwrap();
+        apply(&mut owner, &Action::Complete { carry: None }).unwrap();
+        owner.status_message = None;
+        let mut mirror = App::new();
+        mirror.show_view(owner.view(true));
+
+        mirror.show_export_modal();
+        mirror.status_message = Some("Copied here".to_string());
+        owner.toggle_timer();
+        owner.status_message = None;
+        mirror.show_view(owner.view(false));
+        assert!(matches!(mirror.modal, Some(ModalKind::Export)));
+        assert_eq!(mirror.status_message.as_deref(), Some("Copied here"));
+
+        // Until the owner has something to say
+        owner.status_message = Some("Timer paused".to_string());
+        mirror.show_view(owner.view(false));
+        assert_eq!(mirror.status_message.as_deref(), Some("Timer paused"));
+    }
+
+    #[test]
+    fn start_rejects_lengths_out_of_range() {
+        let mut app = App::new();
+        for seconds in [0, 59, MAX_SESSION_LENGTH + 1, 1 << 62] {
+            let start = Action::Start { session_length: Some(seconds) };
+            assert!(apply(&mut app, &start).unwrap_err().to_string().contains("1440"));
+        }
+        assert!(!app.is_timer_running());
+        assert_eq!(app.active_note.duration, 3600);
+    }
+
+    #[test]
+    fn add_waits_for_a_task_being_edited() {
+        let mut app = App::new();
+        add(&mut app, "first").unwrap();
+        app.selected_todo = 1;
+        app.start_editing();
+        app.edit_str("typing", 1);
+        assert!(add(&mut app, "from a script").unwrap_err().to_string().contains("busy"));
+        assert_eq!(app.input_mode, InputMode::Editing(1));
+        assert_eq!(app.selected_todo, 1);
+        // The edit is still recorded once it's done
+        app.stop_editing();
+        app.undo();
+        assert!(app.active_note.todos[1].text.is_empty());
+        assert_eq!(app.active_note.todos[0].text, "first");
+    }
+
+    #[test]
+    fn add_waits_for_help_to_close() {
+        let mut app = App::new();
+        app.show_help();
+        assert!(add(&mut app, "task").is_err());
+        assert!(matches!(app.modal, Some(ModalKind::Help)));
+        assert_eq!(app.input_mode, InputMode::Modal);
+        app.dismiss_modal();
+        add(&mut app, "task").unwrap();
+    }
+
+    #[test]
+    fn complete_leaves_the_away_prompt_open() {
+        let clock = ManualClock::new(1_000_000);
+        let mut app = App::new().with_clock(clock.clone());
+        add(&mut app, "task").unwrap();
+        app.tick();
+        apply(&mut app, &Action::Start { session_length: None }).unwrap();
+        clock.advance_secs(60);
+        app.tick();
+        clock.jump_wall(40 * 60_000);
+        app.tick();
+        assert!(matches!(app.modal, Some(ModalKind::Away { .. })));
+        let complete = Action::Complete { carry: None };
+        assert!(apply(&mut app, &complete).unwrap_err().to_string().contains("busy"));
+        assert!(matches!(app.modal, Some(ModalKind::Away { seconds: 2400 })));
+        assert!(app.completed_notes.is_empty());
+    }
+
+    #[test]
+    fn actions_read_as_tagged_json() {
+        let action: Action = serde_json::from_str(r#"{"action":"add","text":"x"}"#).unwrap();
+        assert_eq!(action, Action::Add { text: "x".to_string() });
+        let action: Action = serde_json::from_str(r#"{"action":"start"}"#).unwrap();
+        assert_eq!(action, Action::Start { session_length: None });
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Focus => "Focus",
+            Phase::ShortBreak => "Short break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+
+    pub fn is_break(self) -> bool {
+        !matches!(self, Phase::Focus)
+    }
+}
+
+/// Break lengths and scheduling for the work/break cycle.
+/// The focus length is the active note's own duration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleConfig {
+    pub short_break: u64,
+    pub long_break: u64,
+    /// Take a long break instead of a short one after every N focus blocks (0 = never).
+    pub long_break_every: u32,
+    /// Start the next phase automatically when the current one ends.
+    pub auto_start: bool,
+}
+
+impl Default for CycleConfig {
+    fn default() -> Self {
+        Self {
+            short_break: 5 * 60,
+            long_break: 15 * 60,
+            long_break_every: 4,
+            auto_start: false,
+        }
+    }
+}
+
+impl CycleConfig {
+    /// Which break follows the given number of finished focus blocks.
+    pub fn break_after(&self, focus_blocks: u32) -> Phase {
+        if self.long_break_every > 0 && focus_blocks.is_multiple_of(self.long_break_every) {
+            Phase::LongBreak
+        } else {
+            Phase::ShortBreak
+        }
+    }
+
+    pub fn length_of(&self, phase: Phase) -> u64 {
+        match phase {
+            Phase::Focus => 0,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// Countdown for a break. Kept apart from `ActiveNote` so break time never
+/// counts toward the note's time spent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Break {
+    pub phase: Phase,
+    pub duration: u64,
+    pub time_left: u64,
+    pub is_running: bool,
+    pub target_time: Option<i64>,
+}
+
+impl Break {
+    pub fn new(phase: Phase, duration: u64) -> Self {
+        Self {
+            phase,
+            duration,
+            time_left: duration,
+            is_running: false,
+            target_time: None,
+        }
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.duration == 0 {
+            return 1.0;
+        }
+        (self.duration.saturating_sub(self.time_left) as f64 / self.duration as f64).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_break_every_fourth_block() {
+        let config = CycleConfig::default();
+        assert_eq!(config.break_after(1), Phase::ShortBreak);
+        assert_eq!(config.break_after(3), Phase::ShortBreak);
+        assert_eq!(config.break_after(4), Phase::LongBreak);
+        assert_eq!(config.break_after(8), Phase::LongBreak);
+    }
+
+    #[test]
+    fn long_break_disabled() {
+        let config = CycleConfig {
+            long_break_every: 0,
+            ..CycleConfig::default()
+        };
+        assert_eq!(config.break_after(4), Phase::ShortBreak);
+    }
+
+    #[test]
+    fn break_progress() {
+        let mut b = Break::new(Phase::ShortBreak, 300);
+        assert_eq!(b.progress(), 0.0);
+        b.time_left = 150;
+        assert_eq!(b.progress(), 0.5);
+    }
+}
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A single line of text with a cursor. The cursor is a byte offset that
+/// always sits on a grapheme cluster boundary, and the length limit counts
+/// grapheme clusters, so "é" or a flag emoji is one character.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineBuffer {
+    text: String,
+    cursor: usize,
+    max_len: usize,
+}
+
+impl LineBuffer {
+    /// Start editing `text` with the cursor at the end.
+    pub fn new(text: &str, max_len: usize) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.len(),
+            max_len,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the cursor into `text()`.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The text left of the cursor.
+    pub fn before_cursor(&self) -> &str {
+        &self.text[..self.cursor]
+    }
+
+    /// Length in grapheme clusters.
+    pub fn len(&self) -> usize {
+        grapheme_len(&self.text)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let mut buf = [0; 4];
+        self.insert_str(c.encode_utf8(&mut buf));
+    }
+
+    /// Insert at the cursor as much of `s` as fits. Line breaks and tabs
+    /// become spaces and other control characters are dropped, so pasted
+    /// text stays on one line.
+    pub fn insert_str(&mut self, s: &str) {
+        let cleaned: String = s
+            .chars()
+            .filter_map(|c| match c {
+                '\r' | '\n' | '\t' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+        let mut len = self.len();
+        for g in cleaned.graphemes(true) {
+            if len >= self.max_len {
+                break;
+            }
+            self.text.insert_str(self.cursor, g);
+            self.cursor += g.len();
+            len = self.len();
+        }
+    }
+
+    /// Delete the grapheme left of the cursor.
+    pub fn backspace(&mut self) {
+        let start = self.prev_boundary();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete the grapheme under the cursor.
+    pub fn delete(&mut self) {
+        let end = self.next_boundary();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Delete the word left of the cursor along with any spaces after it.
+    pub fn delete_word(&mut self) {
+        let before = self.before_cursor().trim_end();
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete everything left of the cursor.
+    pub fn delete_to_start(&mut self) {
+        self.text.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.prev_boundary();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.before_cursor()
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map(|g| self.cursor + g.len())
+            .unwrap_or(self.cursor)
+    }
+}
+
+/// Number of grapheme clusters in `s`.
+pub fn grapheme_len(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserts_at_cursor() {
+        let mut buf = LineBuffer::new("helo", 50);
+        buf.move_left();
+        buf.insert_char('l');
+        assert_eq!(buf.text(), "hello");
+        assert_eq!(buf.before_cursor(), "hell");
+    }
+
+    #[test]
+    fn moves_over_whole_graphemes() {
+        let mut buf = LineBuffer::new("ae\u{301}b", 50);
+        buf.move_left();
+        buf.move_left();
+        assert_eq!(buf.before_cursor(), "a");
+        buf.move_right();
+        assert_eq!(buf.before_cursor(), "ae\u{301}");
+        buf.backspace();
+        assert_eq!(buf.text(), "ab");
+    }
+
+    #[test]
+    fn home_end_and_delete() {
+        let mut buf = LineBuffer::new("xabc", 50);
+        buf.move_home();
+        buf.delete();
+        assert_eq!(buf.text(), "abc");
+        assert_eq!(buf.cursor(), 0);
+        buf.move_end();
+        buf.delete(); // at end: nothing to delete
+        assert_eq!(buf.text(), "abc");
+        assert_eq!(buf.cursor(), 3);
+    }
+
+    #[test]
+    fn delete_word_takes_trailing_spaces() {
+        let mut buf = LineBuffer::new("write the report  ", 50);
+        buf.delete_word();
+        assert_eq!(buf.text(), "write the ");
+        buf.delete_word();
+        buf.delete_word();
+        assert_eq!(buf.text(), "");
+        buf.delete_word(); // empty: no panic
+    }
+
+    #[test]
+    fn delete_to_start_keeps_text_after_cursor() {
+        let mut buf = LineBuffer::new("old new", 50);
+        buf.move_left();
+        buf.move_left();
+        buf.move_left();
+        buf.delete_to_start();
+        assert_eq!(buf.text(), "new");
+        assert_eq!(buf.cursor(), 0);
+    }
+
+    #[test]
+    fn limit_counts_graphemes() {
+        let mut buf = LineBuffer::new("", 3);
+        buf.insert_str("ééé🇳🇱");
+        assert_eq!(buf.text(), "ééé");
+        assert_eq!(buf.len(), 3);
+
+        let mut buf = LineBuffer::new("", 3);
+        buf.insert_str("🇳🇱🇳🇱🇳🇱🇳🇱");
+        assert_eq!(buf.len(), 3);
+    }
+
+    #[test]
+    fn paste_flattens_line_breaks() {
+        let mut buf = LineBuffer::new("", 50);
+        buf.insert_str("one\r\ntwo\u{7}");
+        assert_eq!(buf.text(), "one  two");
+    }
+}
@@ -0,0 +1,477 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+
+use crate::types::{
+    at_offset, format_adjustment, format_overtime, format_time, local_offset_at, CompletedNote,
+};
+
+/// Turns completed notes into a document.
+pub trait Exporter {
+    /// File extension for the output, without the dot.
+    fn extension(&self) -> &str;
+
+    /// Start of exported file names, before the date.
+    fn file_stem(&self) -> &str {
+        "one-good-hour"
+    }
+
+    fn render(&self, notes: &[CompletedNote]) -> Result<String>;
+}
+
+/// The built-in export formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Json,
+    Csv,
+    Ical,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Markdown, Format::Json, Format::Csv, Format::Ical];
+
+    pub fn exporter(self) -> &'static dyn Exporter {
+        match self {
+            Format::Markdown => &Markdown,
+            Format::Json => &Json,
+            Format::Csv => &Csv,
+            Format::Ical => &Ical,
+        }
+    }
+
+    /// The format whose extension `path` ends in, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|f| f.exporter().extension().eq_ignore_ascii_case(ext))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Markdown => "Markdown",
+            Format::Json => "JSON",
+            Format::Csv => "CSV",
+            Format::Ical => "iCalendar",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Parses `md`/`markdown`, `json`, `csv` or `ics`/`ical`.
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "ics" | "ical" | "icalendar" => Ok(Format::Ical),
+            _ => Err(format!("unknown format '{s}', expected md, json, csv or ics")),
+        }
+    }
+}
+
+/// `one-good-hour-2026-10-17.md` and so on, for the local date at `now_ms`.
+pub fn default_file_name(exporter: &dyn Exporter, now_ms: i64) -> String {
+    let date = at_offset(now_ms, local_offset_at(now_ms)).format("%Y-%m-%d");
+    format!("{}-{date}.{}", exporter.file_stem(), exporter.extension())
+}
+
+/// Render `notes` and write them to `path`.
+pub fn write_file(exporter: &dyn Exporter, notes: &[CompletedNote], path: &Path) -> Result<()> {
+    let text = exporter.render(notes)?;
+    fs::write(path, text).with_context(|| format!("writing {}", path.display()))
+}
+
+/// The notes as Markdown, one section per session: what the clipboard gets.
+pub struct Markdown;
+
+impl Exporter for Markdown {
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(&self, notes: &[CompletedNote]) -> Result<String> {
+        Ok(markdown(notes))
+    }
+}
+
+pub fn markdown(notes: &[CompletedNote]) -> String {
+    let mut md = String::from("# One Good Hour\n\n");
+    for note in notes {
+        md.push_str(&format!("## Session {}\n", note.completion_number));
+        if let Some(span) = note.span() {
+            md.push_str(&format!("When: {span}\n"));
+        }
+        if note.runs.len() > 1 {
+            let list: Vec<String> = note
+                .runs
+                .iter()
+                .map(|r| {
+                    let start = at_offset(r.started_at, note.utc_offset);
+                    let end = at_offset(r.ended_at.unwrap_or(note.finished_at), note.utc_offset);
+                    format!("{}–{}", start.format("%H:%M"), end.format("%H:%M"))
+                })
+                .collect();
+            md.push_str(&format!("Runs: {}\n", list.join("; ")));
+        }
+        md.push_str(&format!(
+            "Time spent: {} of {}",
+            format_time(note.time_spent),
+            format_time(note.duration)
+        ));
+        if note.overtime > 0 {
+            md.push_str(&format!(" ({} overtime)", format_overtime(note.overtime)));
+        }
+        md.push('\n');
+        if note.interruptions > 0 {
+            md.push_str(&format!(
+                "Interruptions: {} ({} paused)\n",
+                note.interruptions,
+                format_time(note.paused_time)
+            ));
+        }
+        if !note.adjustments.is_empty() {
+            let list: Vec<String> = note
+                .adjustments
+                .iter()
+                .map(|a| format_adjustment(a.seconds))
+                .collect();
+            md.push_str(&format!("Adjusted: {}\n", list.join(", ")));
+        }
+        md.push('\n');
+        for todo in &note.todos {
+            if !todo.text.is_empty() {
+                let check = if todo.completed { "x" } else { " " };
+                md.push_str(&format!("- [{}] {}", check, todo.text));
+                let mut notes = Vec::new();
+                if todo.time_spent > 0 {
+                    notes.push(format_time(todo.time_spent));
+                }
+                if let Some(from) = todo.carried_from {
+                    notes.push(format!("from session {from}"));
+                }
+                if !notes.is_empty() {
+                    md.push_str(&format!(" ({})", notes.join(", ")));
+                }
+                md.push('\n');
+            }
+        }
+        md.push('\n');
+    }
+    md
+}
+
+/// The notes exactly as stored, as a pretty-printed JSON array.
+pub struct Json;
+
+impl Exporter for Json {
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, notes: &[CompletedNote]) -> Result<String> {
+        let mut json = serde_json::to_string_pretty(notes)?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// One row per task, with its session's details repeated on each row.
+/// Durations are in seconds and times are RFC 3339.
+pub struct Csv;
+
+const CSV_HEADER: &str = "session,started_at,finished_at,duration,time_spent,\
+task,completed,completed_at,task_time_spent,carried_from";
+
+impl Exporter for Csv {
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn render(&self, notes: &[CompletedNote]) -> Result<String> {
+        let mut csv = format!("{CSV_HEADER}\n");
+        for note in notes {
+            // Notes saved before timestamps were recorded have none
+            let time = |ms: i64| {
+                if note.finished_at > 0 {
+                    at_offset(ms, note.utc_offset).to_rfc3339()
+                } else {
+                    String::new()
+                }
+            };
+            for todo in note.todos.iter().filter(|t| !t.text.is_empty()) {
+                let fields = [
+                    note.completion_number.to_string(),
+                    time(note.started_at),
+                    time(note.finished_at),
+                    note.duration.to_string(),
+                    note.time_spent.to_string(),
+                    csv_field(&todo.text),
+                    todo.completed.to_string(),
+                    todo.completed_at.map(time).unwrap_or_default(),
+                    todo.time_spent.to_string(),
+                    todo.carried_from.map(|n| n.to_string()).unwrap_or_default(),
+                ];
+                csv.push_str(&fields.join(","));
+                csv.push('\n');
+            }
+        }
+        Ok(csv)
+    }
+}
+
+/// An iCalendar (RFC 5545) calendar with one event per session, titled
+/// with its tasks and described by its checklist. Notes saved before
+/// timestamps were recorded can't be placed and are left out.
+pub struct Ical;
+
+impl Exporter for Ical {
+    fn extension(&self) -> &str {
+        "ics"
+    }
+
+    fn render(&self, notes: &[CompletedNote]) -> Result<String> {
+        let mut ics = String::new();
+        let mut line = |s: &str| ics.push_str(&fold_ical_line(s));
+        line("BEGIN:VCALENDAR");
+        line("VERSION:2.0");
+        line("PRODID:-//One Good Hour//1gh//EN");
+        line("CALSCALE:GREGORIAN");
+        for note in notes.iter().filter(|n| n.finished_at > 0) {
+            let tasks: Vec<&str> = note
+                .todos
+                .iter()
+                .filter(|t| !t.text.is_empty())
+                .map(|t| t.text.as_str())
+                .collect();
+            let summary = if tasks.is_empty() {
+                "Focus session".to_string()
+            } else {
+                tasks.join(", ")
+            };
+            let mut description: Vec<String> = note
+                .todos
+                .iter()
+                .filter(|t| !t.text.is_empty())
+                .map(|t| format!("[{}] {}", if t.completed { "x" } else { " " }, t.text))
+                .collect();
+            description.push(format!(
+                "Time spent: {} of {}",
+                format_time(note.time_spent),
+                format_time(note.duration)
+            ));
+
+            line("BEGIN:VEVENT");
+            // Session numbers shift as history is merged or cleared, so
+            // the times alone identify the event
+            line(&format!("UID:{}-{}@onegoodhour.com", note.started_at, note.finished_at));
+            line(&format!("DTSTAMP:{}", ical_time(note.finished_at)));
+            line(&format!("DTSTART:{}", ical_time(note.started_at)));
+            line(&format!("DTEND:{}", ical_time(note.finished_at)));
+            line(&format!("SUMMARY:{}", ical_text(&summary)));
+            line(&format!("DESCRIPTION:{}", ical_text(&description.join("\n"))));
+            line("END:VEVENT");
+        }
+        line("END:VCALENDAR");
+        Ok(ics)
+    }
+}
+
+/// A UTC date-time such as `20261017T120300Z`.
+fn ical_time(ms: i64) -> String {
+    at_offset(ms, 0).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value: backslashes, commas, semicolons and line breaks.
+fn ical_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// End a content line with CRLF, folding it so no line is longer than 75
+/// octets. Continuation lines start with a space; characters aren't split.
+fn fold_ical_line(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    let mut width = 0;
+    for c in s.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// Quote a field if it holds a comma, quote or line break (RFC 4180).
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Todo;
+
+    /// 2026-10-17 12:03 UTC.
+    const START_MS: i64 = 1_792_238_580_000;
+
+    fn note() -> CompletedNote {
+        CompletedNote {
+            started_at: START_MS,
+            finished_at: START_MS + 1_200_000,
+            utc_offset: 7200,
+            todos: vec![
+                Todo {
+                    text: "write tests".to_string(),
+                    completed: true,
+                    time_spent: 300,
+                    completed_at: Some(START_MS + 600_000),
+                    ..Todo::default()
+                },
+                Todo {
+                    text: "review \"big\" PR, then merge".to_string(),
+                    carried_from: Some(1),
+                    ..Todo::default()
+                },
+                Todo::default(),
+            ],
+            time_spent: 1200,
+            duration: 3600,
+            overtime: 0,
+            runs: Vec::new(),
+            interruptions: 0,
+            paused_time: 0,
+            adjustments: Vec::new(),
+            completion_number: 2,
+        }
+    }
+
+    #[test]
+    fn markdown_lists_sessions_and_tasks() {
+        let md = Markdown.render(&[note()]).unwrap();
+        assert!(md.starts_with("# One Good Hour\n\n## Session 2\n"));
+        assert!(md.contains("When: 2026-10-17 14:03–14:23 +02:00\n"));
+        assert!(md.contains("Time spent: 20:00 of 60:00\n"));
+        assert!(md.contains("- [x] write tests (5:00)\n"));
+        assert!(md.contains("- [ ] review \"big\" PR, then merge (from session 1)\n"));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let json = Json.render(&[note()]).unwrap();
+        let notes: Vec<CompletedNote> = serde_json::from_str(&json).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].todos[0].text, "write tests");
+        assert_eq!(notes[0].started_at, START_MS);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_task() {
+        let csv = Csv.render(&[note()]).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "2,2026-10-17T14:03:00+02:00,2026-10-17T14:23:00+02:00,3600,1200,\
+write tests,true,2026-10-17T14:13:00+02:00,300,"
+        );
+        assert!(lines[2].contains(",\"review \"\"big\"\" PR, then merge\",false,,0,1"));
+    }
+
+    #[test]
+    fn old_notes_export_without_times() {
+        let old = CompletedNote {
+            started_at: 0,
+            finished_at: 0,
+            utc_offset: 0,
+            ..note()
+        };
+        let csv = Csv.render(std::slice::from_ref(&old)).unwrap();
+        assert!(csv.lines().nth(1).unwrap().starts_with("2,,,3600"));
+        assert!(!Markdown.render(&[old]).unwrap().contains("When:"));
+    }
+
+    #[test]
+    fn ical_has_an_event_per_timed_session() {
+        let old = CompletedNote {
+            started_at: 0,
+            finished_at: 0,
+            ..note()
+        };
+        let ics = Ical.render(&[note(), old]).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("DTSTART:20261017T120300Z\r\n"));
+        assert!(ics.contains("DTEND:20261017T122300Z\r\n"));
+        let uid = format!("UID:{}-{}@onegoodhour.com", note().started_at, note().finished_at);
+        assert!(ics.contains(&uid));
+        let renumbered = CompletedNote {
+            completion_number: 9,
+            ..note()
+        };
+        assert!(Ical.render(&[renumbered]).unwrap().contains(&uid));
+        assert!(ics.contains("SUMMARY:write tests\\, review \"big\" PR\\, then merge\r\n"));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(
+            "DESCRIPTION:[x] write tests\\n[ ] review \"big\" PR\\, then merge\\nTime spent: 20:00 of 60:00\r\n"
+        ));
+    }
+
+    #[test]
+    fn ical_lines_fold_at_75_octets() {
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_ical_line(&line);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+        assert_eq!(ical_text("a;b\\c\r\nd"), "a\\;b\\\\c\\nd");
+    }
+
+    #[test]
+    fn formats_parse_and_match_extensions() {
+        assert_eq!("md".parse(), Ok(Format::Markdown));
+        assert_eq!("JSON".parse(), Ok(Format::Json));
+        assert_eq!("ics".parse(), Ok(Format::Ical));
+        assert!("xml".parse::<Format>().is_err());
+        assert_eq!(Format::from_path(Path::new("out/history.CSV")), Some(Format::Csv));
+        assert_eq!(Format::from_path(Path::new("history")), None);
+    }
+
+    #[test]
+    fn default_file_name_uses_extension() {
+        let name = default_file_name(Format::Json.exporter(), START_MS);
+        assert!(name.starts_with("one-good-hour-2026-10-1"));
+        assert!(name.ends_with(".json"));
+    }
+}
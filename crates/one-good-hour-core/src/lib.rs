@@ -1,2 +1,12 @@
 pub mod app;
+pub mod clock;
+pub mod control;
+pub mod cycle;
+pub mod editor;
+pub mod export;
+pub mod milestone;
+pub mod stats;
+pub mod store;
+pub mod template;
 pub mod types;
+pub mod undo;
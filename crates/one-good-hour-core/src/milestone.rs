@@ -0,0 +1,113 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::format_time;
+
+/// A point in a focus session worth an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Milestone {
+    Halfway,
+    /// Seconds after the session started.
+    Elapsed(u64),
+    /// Seconds before the planned end.
+    Remaining(u64),
+}
+
+pub fn default_milestones() -> Vec<Milestone> {
+    vec![Milestone::Halfway, Milestone::Remaining(5 * 60)]
+}
+
+impl Milestone {
+    /// Elapsed seconds at which this milestone fires in a session of
+    /// `duration` seconds. `None` if it falls outside the session.
+    pub fn at_elapsed(self, duration: u64) -> Option<u64> {
+        let at = match self {
+            Milestone::Halfway => duration / 2,
+            Milestone::Elapsed(secs) => secs,
+            Milestone::Remaining(secs) => duration.checked_sub(secs)?,
+        };
+        (at > 0 && at < duration).then_some(at)
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Milestone::Halfway => "Halfway there".to_string(),
+            Milestone::Elapsed(secs) => format!("{} in", format_time(secs)),
+            Milestone::Remaining(secs) => format!("{} left", format_time(secs)),
+        }
+    }
+}
+
+impl fmt::Display for Milestone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label())
+    }
+}
+
+/// Parses `half`, `at=<minutes>` or `left=<minutes>`.
+impl FromStr for Milestone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "half" {
+            return Ok(Milestone::Halfway);
+        }
+        let (kind, minutes) = s
+            .split_once('=')
+            .ok_or_else(|| format!("invalid milestone '{s}', expected half, at=<min> or left=<min>"))?;
+        let minutes: u64 = minutes
+            .parse()
+            .map_err(|_| format!("invalid milestone minutes '{minutes}'"))?;
+        let seconds = minutes
+            .checked_mul(60)
+            .ok_or_else(|| format!("milestone minutes '{minutes}' out of range"))?;
+        match kind {
+            "at" => Ok(Milestone::Elapsed(seconds)),
+            "left" => Ok(Milestone::Remaining(seconds)),
+            _ => Err(format!("invalid milestone '{s}', expected half, at=<min> or left=<min>")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_elapsed_positions() {
+        assert_eq!(Milestone::Halfway.at_elapsed(3600), Some(1800));
+        assert_eq!(Milestone::Elapsed(600).at_elapsed(3600), Some(600));
+        assert_eq!(Milestone::Remaining(300).at_elapsed(3600), Some(3300));
+    }
+
+    #[test]
+    fn at_elapsed_outside_session_is_none() {
+        assert_eq!(Milestone::Remaining(30 * 60).at_elapsed(25 * 60), None);
+        assert_eq!(Milestone::Elapsed(25 * 60).at_elapsed(25 * 60), None);
+        assert_eq!(Milestone::Elapsed(0).at_elapsed(25 * 60), None);
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(Milestone::Halfway.label(), "Halfway there");
+        assert_eq!(Milestone::Remaining(300).label(), "5:00 left");
+        assert_eq!(Milestone::Elapsed(1500).label(), "25:00 in");
+    }
+
+    #[test]
+    fn parse_specs() {
+        assert_eq!("half".parse(), Ok(Milestone::Halfway));
+        assert_eq!("at=25".parse(), Ok(Milestone::Elapsed(1500)));
+        assert_eq!("left=5".parse(), Ok(Milestone::Remaining(300)));
+        assert!("soon".parse::<Milestone>().is_err());
+        assert!("left=x".parse::<Milestone>().is_err());
+    }
+
+    #[test]
+    fn parse_rejects_overflowing_minutes() {
+        let spec = format!("at={}", u64::MAX / 30);
+        assert!(spec.parse::<Milestone>().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use chrono::{Days, NaiveDate, Weekday};
+
+use crate::types::{at_offset, CompletedNote};
+
+/// Focused seconds a day needs to count towards a streak.
+pub const GOOD_HOUR: u64 = 60 * 60;
+
+/// Focus on one calendar day, in the time zone each session ran in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayTotal {
+    pub date: NaiveDate,
+    pub seconds: u64,
+    pub sessions: u32,
+}
+
+impl DayTotal {
+    pub fn is_good(&self) -> bool {
+        self.seconds >= GOOD_HOUR
+    }
+}
+
+/// Focus in one week, Monday to Sunday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekTotal {
+    /// The Monday the week starts on.
+    pub start: NaiveDate,
+    pub seconds: u64,
+    pub sessions: u32,
+}
+
+/// Totals over a set of completed notes. Notes saved before timestamps were
+/// recorded count towards everything except the per-day and per-week totals
+/// and the streaks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub sessions: usize,
+    /// Focused seconds, overtime included.
+    pub focused: u64,
+    /// Days with any focus, oldest first.
+    pub days: Vec<DayTotal>,
+    /// Weeks with any focus, oldest first.
+    pub weeks: Vec<WeekTotal>,
+    pub todos_completed: usize,
+    /// Tasks that had text when their session was completed.
+    pub todos_planned: usize,
+    /// Days in a row with a good hour, ending today, or yesterday if today
+    /// has none yet.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+impl Stats {
+    pub fn compute(notes: &[CompletedNote], today: NaiveDate) -> Self {
+        let mut stats = Stats {
+            sessions: notes.len(),
+            ..Stats::default()
+        };
+        let mut days: BTreeMap<NaiveDate, DayTotal> = BTreeMap::new();
+        for note in notes {
+            let focused = note.time_spent + note.overtime;
+            stats.focused += focused;
+            stats.todos_planned += note.todos.iter().filter(|t| !t.text.is_empty()).count();
+            stats.todos_completed += note
+                .todos
+                .iter()
+                .filter(|t| t.completed && !t.text.is_empty())
+                .count();
+            if note.finished_at > 0 {
+                let date = at_offset(note.started_at, note.utc_offset).date_naive();
+                let day = days.entry(date).or_insert(DayTotal {
+                    date,
+                    seconds: 0,
+                    sessions: 0,
+                });
+                day.seconds += focused;
+                day.sessions += 1;
+            }
+        }
+
+        let mut weeks: BTreeMap<NaiveDate, WeekTotal> = BTreeMap::new();
+        for day in days.values() {
+            let start = day.date.week(Weekday::Mon).first_day();
+            let week = weeks.entry(start).or_insert(WeekTotal {
+                start,
+                seconds: 0,
+                sessions: 0,
+            });
+            week.seconds += day.seconds;
+            week.sessions += day.sessions;
+        }
+
+        stats.days = days.into_values().collect();
+        stats.weeks = weeks.into_values().collect();
+        stats.longest_streak = longest_streak(&stats.days);
+        stats.current_streak = current_streak(&stats.days, today);
+        stats
+    }
+
+    /// Average focused seconds per session.
+    pub fn average_session(&self) -> u64 {
+        if self.sessions == 0 {
+            return 0;
+        }
+        self.focused / self.sessions as u64
+    }
+
+    /// Share of planned tasks that were checked off, in [0.0, 1.0]; `None`
+    /// with nothing planned.
+    pub fn completion_rate(&self) -> Option<f64> {
+        (self.todos_planned > 0).then(|| self.todos_completed as f64 / self.todos_planned as f64)
+    }
+
+    /// Focused seconds on `date`.
+    pub fn day(&self, date: NaiveDate) -> u64 {
+        self.days
+            .binary_search_by_key(&date, |d| d.date)
+            .map_or(0, |i| self.days[i].seconds)
+    }
+
+    /// The `n` days up to and including `today`, oldest first, with empty
+    /// days filled in.
+    pub fn recent_days(&self, today: NaiveDate, n: u64) -> Vec<DayTotal> {
+        (0..n)
+            .rev()
+            .map(|back| today - Days::new(back))
+            .map(|date| match self.days.binary_search_by_key(&date, |d| d.date) {
+                Ok(i) => self.days[i],
+                Err(_) => DayTotal {
+                    date,
+                    seconds: 0,
+                    sessions: 0,
+                },
+            })
+            .collect()
+    }
+
+    /// A calendar of the `weeks` weeks up to and including this one: one
+    /// column per week, oldest first, each running Monday to Sunday. Days
+    /// after `today` are `None`.
+    pub fn calendar(&self, today: NaiveDate, weeks: u64) -> Vec<[Option<DayTotal>; 7]> {
+        let this_week = today.week(Weekday::Mon).first_day();
+        let n = (today - this_week).num_days() as u64 + 1 + (weeks.max(1) - 1) * 7;
+        let days = self.recent_days(today, n);
+        days.chunks(7)
+            .map(|week| std::array::from_fn(|i| week.get(i).copied()))
+            .collect()
+    }
+
+    /// Focused seconds in the week containing `date`.
+    pub fn week(&self, date: NaiveDate) -> u64 {
+        let start = date.week(Weekday::Mon).first_day();
+        self.weeks
+            .binary_search_by_key(&start, |w| w.start)
+            .map_or(0, |i| self.weeks[i].seconds)
+    }
+}
+
+fn longest_streak(days: &[DayTotal]) -> u32 {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut last: Option<NaiveDate> = None;
+    for day in days.iter().filter(|d| d.is_good()) {
+        let follows = last.is_some_and(|l| l.checked_add_days(Days::new(1)) == Some(day.date));
+        run = if follows { run + 1 } else { 1 };
+        longest = longest.max(run);
+        last = Some(day.date);
+    }
+    longest
+}
+
+fn current_streak(days: &[DayTotal], today: NaiveDate) -> u32 {
+    let good = |date: NaiveDate| {
+        days.binary_search_by_key(&date, |d| d.date)
+            .is_ok_and(|i| days[i].is_good())
+    };
+    // Today still counts as part of yesterday's streak until it's over
+    let mut date = if good(today) { today } else { today - Days::new(1) };
+    let mut streak = 0;
+    while good(date) {
+        streak += 1;
+        date = date - Days::new(1);
+    }
+    streak
+}
+
+/// Whole minutes, shown as "2h 05m" or "45m".
+pub fn format_minutes(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Todo;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    /// A session of `minutes` starting at 10:00 UTC on October `day`.
+    fn note(day: u32, minutes: u64, done: usize, planned: usize) -> CompletedNote {
+        let start = date(day).and_hms_opt(10, 0, 0).unwrap().and_utc().timestamp_millis();
+        let todos = (0..planned)
+            .map(|i| Todo {
+                text: format!("task {i}"),
+                completed: i < done,
+                ..Todo::default()
+            })
+            .chain([Todo::default()])
+            .collect();
+        CompletedNote {
+            started_at: start,
+            finished_at: start + minutes as i64 * 60_000,
+            utc_offset: 0,
+            todos,
+            time_spent: minutes * 60,
+            duration: 3600,
+            overtime: 0,
+            runs: Vec::new(),
+            interruptions: 0,
+            paused_time: 0,
+            adjustments: Vec::new(),
+            completion_number: 1,
+        }
+    }
+
+    #[test]
+    fn totals_by_day_and_week() {
+        // Thu 15, Fri 16 and Mon 19 October 2026
+        let notes = [note(15, 30, 1, 2), note(15, 45, 2, 2), note(16, 60, 0, 3), note(19, 20, 0, 0)];
+        let stats = Stats::compute(&notes, date(19));
+        assert_eq!(stats.sessions, 4);
+        assert_eq!(stats.focused, 155 * 60);
+        assert_eq!(stats.day(date(15)), 75 * 60);
+        assert_eq!(stats.days[0].sessions, 2);
+        assert_eq!(stats.day(date(17)), 0);
+        assert_eq!(stats.weeks.len(), 2);
+        assert_eq!(stats.weeks[0].start, date(12));
+        assert_eq!(stats.week(date(18)), 135 * 60);
+        assert_eq!(stats.week(date(20)), 20 * 60);
+        assert_eq!(stats.average_session(), 155 * 60 / 4);
+        let recent: Vec<u64> = stats.recent_days(date(19), 5).iter().map(|d| d.seconds / 60).collect();
+        assert_eq!(recent, [75, 60, 0, 0, 20]);
+        assert_eq!(stats.recent_days(date(19), 5)[0].date, date(15));
+    }
+
+    #[test]
+    fn calendar_has_monday_to_sunday_columns() {
+        let stats = Stats::compute(&[note(12, 30, 0, 0), note(16, 60, 0, 0)], date(17));
+        // Saturday 17 October: this week and the one before
+        let calendar = stats.calendar(date(17), 2);
+        assert_eq!(calendar.len(), 2);
+        assert_eq!(calendar[0][0].unwrap().date, date(5));
+        assert_eq!(calendar[1][0].unwrap().seconds, 30 * 60);
+        assert_eq!(calendar[1][4].unwrap().seconds, 60 * 60);
+        assert_eq!(calendar[1][5].unwrap().date, date(17));
+        assert!(calendar[1][6].is_none());
+    }
+
+    #[test]
+    fn completion_rate_counts_only_tasks_with_text() {
+        let stats = Stats::compute(&[note(15, 30, 1, 2), note(16, 30, 2, 2)], date(16));
+        assert_eq!((stats.todos_completed, stats.todos_planned), (3, 4));
+        assert_eq!(stats.completion_rate(), Some(0.75));
+        assert_eq!(Stats::compute(&[], date(16)).completion_rate(), None);
+    }
+
+    #[test]
+    fn streaks_need_a_good_hour_a_day() {
+        let notes = [
+            note(1, 60, 0, 0),
+            note(2, 30, 0, 0),
+            note(2, 30, 0, 0), // two halves make a good hour
+            note(3, 90, 0, 0),
+            note(5, 59, 0, 0), // not quite
+            note(8, 60, 0, 0),
+            note(9, 60, 0, 0),
+        ];
+        let stats = Stats::compute(&notes, date(9));
+        assert_eq!(stats.longest_streak, 3);
+        assert_eq!(stats.current_streak, 2);
+        // Today isn't over: yesterday's streak still stands
+        assert_eq!(Stats::compute(&notes, date(10)).current_streak, 2);
+        assert_eq!(Stats::compute(&notes, date(11)).current_streak, 0);
+    }
+
+    #[test]
+    fn notes_without_timestamps_skip_days() {
+        let old = CompletedNote {
+            started_at: 0,
+            finished_at: 0,
+            ..note(1, 60, 1, 1)
+        };
+        let stats = Stats::compute(&[old], date(1));
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(stats.focused, 3600);
+        assert!(stats.days.is_empty());
+        assert_eq!(stats.longest_streak, 0);
+    }
+
+    #[test]
+    fn formats_minutes() {
+        assert_eq!(format_minutes(45 * 60 + 59), "45m");
+        assert_eq!(format_minutes(125 * 60), "2h 05m");
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::GapPolicy;
+use crate::cycle::{Break, CycleConfig};
+use crate::milestone::Milestone;
+use crate::types::{ActiveNote, CompletedNote};
+
+/// Version written to new history and recovery files. Bump it when the
+/// layout changes in a way older builds can't read, and migrate older
+/// versions in `load`.
+pub const FORMAT_VERSION: u32 = 1;
+
+const APP_DIR: &str = "one-good-hour";
+const FILE_NAME: &str = "history.json";
+const RECOVERY_FILE_NAME: &str = "session.json";
+
+/// `$XDG_DATA_HOME/one-good-hour`, falling back to `~/.local/share` when
+/// `XDG_DATA_HOME` is unset.
+pub fn data_dir() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))?;
+    Some(data_home.join(APP_DIR))
+}
+
+/// `$XDG_CONFIG_HOME/one-good-hour`, falling back to `~/.config` when
+/// `XDG_CONFIG_HOME` is unset.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config_home.join(APP_DIR))
+}
+
+/// Write `data` next to `path` and rename it over the target, so a crash
+/// never leaves a half-written file. Each write gets a temporary file of its
+/// own, so concurrent writers never share one.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let dir = path.parent().context("path has no parent directory")?;
+    fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    let name = path.file_name().context("path has no file name")?.to_string_lossy();
+    let write = WRITES.fetch_add(1, Ordering::Relaxed);
+    let tmp = dir.join(format!(".{name}.{}-{write}.tmp", std::process::id()));
+    let mut out = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+    let written = out
+        .write_all(data)
+        .and_then(|()| out.sync_all())
+        .and_then(|()| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written.with_context(|| format!("replacing {}", path.display()))
+}
+
+/// Hold an exclusive advisory lock on `<path>.lock` until the returned file
+/// is dropped. The lock file stays put, since the file it guards is replaced
+/// on every write.
+fn lock(path: &Path) -> Result<File> {
+    let dir = path.parent().context("path has no parent directory")?;
+    fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    let lock_path = path.with_extension("lock");
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("opening {}", lock_path.display()))?;
+    file.lock().with_context(|| format!("locking {}", lock_path.display()))?;
+    Ok(file)
+}
+
+/// Read `path`, or `None` if it doesn't exist.
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+fn check_version(path: &Path, version: u32) -> Result<()> {
+    if version > FORMAT_VERSION {
+        bail!(
+            "{} was written by a newer version (format {version})",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    notes: Vec<CompletedNote>,
+}
+
+/// Completed notes saved as JSON under the XDG data directory. The TUI and
+/// the desktop app use the same file.
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    path: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `history.json` in `data_dir()`.
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|d| d.join(FILE_NAME))
+    }
+
+    /// The store at the default path.
+    pub fn open_default() -> Option<Self> {
+        Self::default_path().map(Self::new)
+    }
+
+    /// The store at the default path, only if a history file is already there
+    /// (i.e. saving was turned on before, from either app).
+    pub fn existing() -> Option<Self> {
+        Self::open_default().filter(|s| s.path.exists())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read all saved notes. A missing file is an empty history.
+    pub fn load(&self) -> Result<Vec<CompletedNote>> {
+        let Some(data) = read_optional(&self.path)? else {
+            return Ok(Vec::new());
+        };
+        let file: HistoryFile = serde_json::from_str(&data)
+            .with_context(|| format!("parsing {}", self.path.display()))?;
+        check_version(&self.path, file.version)?;
+        Ok(file.notes)
+    }
+
+    /// Save `notes`, merged with whatever other processes saved since this
+    /// one last loaded or saved `base`, and return the merged history.
+    /// Holds the store's lock from reading to writing, so saves from the TUI
+    /// and the desktop app never undo each other.
+    pub fn save(&self, base: &[CompletedNote], notes: &[CompletedNote]) -> Result<MergedNotes> {
+        let _lock = lock(&self.path)?;
+        let merged = merge_notes(base, &self.load()?, notes);
+        let file = HistoryFile {
+            version: FORMAT_VERSION,
+            notes: merged.notes,
+        };
+        write_atomic(&self.path, &serde_json::to_vec_pretty(&file)?)?;
+        Ok(MergedNotes {
+            notes: file.notes,
+            renumbered: merged.renumbered,
+        })
+    }
+}
+
+/// Completed notes after a merge, numbered from 1 in order of completion.
+#[derive(Debug, Clone)]
+pub struct MergedNotes {
+    pub notes: Vec<CompletedNote>,
+    /// The new number of each merged-in note, by its old number.
+    pub renumbered: HashMap<u64, u64>,
+}
+
+/// Tells notes apart across processes: a note's start and end, and the
+/// rest of it for notes finished in the same instant. Numbers can't, as
+/// each process numbers the notes it completes itself.
+pub(crate) fn note_key(note: &CompletedNote) -> String {
+    let mut note = note.clone();
+    note.completion_number = 0;
+    for todo in &mut note.todos {
+        todo.carried_from = None;
+    }
+    serde_json::to_string(&note).unwrap_or_default()
+}
+
+/// Merge `notes`, which started out as `base`, into `saved`: notes removed
+/// since `base` are removed, notes added are added once, and the rest of
+/// `saved` is kept. Carried tasks follow their session to its new number.
+pub(crate) fn merge_notes(
+    base: &[CompletedNote],
+    saved: &[CompletedNote],
+    notes: &[CompletedNote],
+) -> MergedNotes {
+    let count = |list: &[CompletedNote]| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for note in list {
+            *counts.entry(note_key(note)).or_default() += 1;
+        }
+        counts
+    };
+    let (in_base, in_saved, in_notes) = (count(base), count(saved), count(notes));
+    let mut keep: HashMap<String, usize> = in_saved
+        .keys()
+        .chain(in_notes.keys())
+        .map(|key| {
+            let get = |counts: &HashMap<_, usize>| counts.get(key).copied().unwrap_or(0);
+            let (b, s, n) = (get(&in_base), get(&in_saved), get(&in_notes));
+            (key.clone(), (s + n).saturating_sub(b).min(s.max(n)))
+        })
+        .collect();
+
+    let mut merged: Vec<(usize, CompletedNote)> = Vec::new();
+    for (source, list) in [saved, notes].into_iter().enumerate() {
+        for note in list {
+            if let Some(left) = keep.get_mut(&note_key(note)).filter(|left| **left > 0) {
+                *left -= 1;
+                merged.push((source, note.clone()));
+            }
+        }
+    }
+    merged.sort_by_key(|(_, note)| note.finished_at);
+
+    let new_numbers: HashMap<String, u64> = merged
+        .iter()
+        .enumerate()
+        .map(|(i, (_, note))| (note_key(note), i as u64 + 1))
+        .collect();
+    let renumber = |list: &[CompletedNote]| -> HashMap<u64, u64> {
+        list.iter()
+            .filter_map(|n| Some((n.completion_number, *new_numbers.get(&note_key(n))?)))
+            .collect()
+    };
+    let numbers = [renumber(saved), renumber(notes)];
+    let notes = merged
+        .into_iter()
+        .enumerate()
+        .map(|(i, (source, mut note))| {
+            note.completion_number = i as u64 + 1;
+            for from in note.todos.iter_mut().filter_map(|t| t.carried_from.as_mut()) {
+                if let Some(&new) = numbers[source].get(from) {
+                    *from = new;
+                }
+            }
+            note
+        })
+        .collect();
+    let [_, renumbered] = numbers;
+    MergedNotes { notes, renumbered }
+}
+
+/// The in-progress note as last saved by a running app.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    /// Unix milliseconds when the checkpoint was written.
+    pub saved_at: i64,
+    /// The process that wrote it; 0 in files from before this was recorded.
+    #[serde(default)]
+    pub pid: u32,
+    pub note: ActiveNote,
+    /// The break under way instead of the note, if any.
+    #[serde(default)]
+    pub on_break: Option<Break>,
+    /// Focus blocks finished toward the next long break.
+    #[serde(default)]
+    pub focus_blocks: u32,
+    /// `None` in files from before settings were kept.
+    #[serde(default)]
+    pub settings: Option<SessionSettings>,
+}
+
+/// The settings a checkpointed session runs under, kept so the session
+/// commands carry them on from one run to the next.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSettings {
+    pub cycle: CycleConfig,
+    pub allow_overtime: bool,
+    pub gap_policy: GapPolicy,
+    pub milestones: Vec<Milestone>,
+    pub todo_cap: usize,
+    pub carry_over: bool,
+}
+
+impl Checkpoint {
+    /// A checkpoint of `note` alone, written at `saved_at`.
+    pub fn new(note: ActiveNote, saved_at: i64) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            saved_at,
+            pid: std::process::id(),
+            note,
+            on_break: None,
+            focus_blocks: 0,
+            settings: None,
+        }
+    }
+
+    /// Whether another process that is still running wrote this, and may
+    /// write it again.
+    pub fn is_held_elsewhere(&self) -> bool {
+        self.pid != 0 && self.pid != std::process::id() && process_alive(self.pid)
+    }
+
+    fn is_mine(&self) -> bool {
+        self.pid == std::process::id()
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks; EPERM means it exists under another user
+    // SAFETY: kill with signal 0 sends nothing.
+    let sent = unsafe { libc::kill(pid, 0) } == 0;
+    sent || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// No cheap check here; treat the writer as gone, as before pids were kept.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    false
+}
+
+/// A copy of the active note kept on disk so a crashed or closed app can
+/// offer to pick up where it left off. Every app uses the same file, so each
+/// checkpoint records its writer, and only that process changes it while
+/// it runs. Changes are made under a lock.
+#[derive(Debug, Clone)]
+pub struct RecoveryFile {
+    path: PathBuf,
+}
+
+impl RecoveryFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `session.json` in `data_dir()`.
+    pub fn open_default() -> Option<Self> {
+        data_dir().map(|d| Self::new(d.join(RECOVERY_FILE_NAME)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Option<Checkpoint>> {
+        let Some(data) = read_optional(&self.path)? else {
+            return Ok(None);
+        };
+        let checkpoint: Checkpoint = serde_json::from_str(&data)
+            .with_context(|| format!("parsing {}", self.path.display()))?;
+        check_version(&self.path, checkpoint.version)?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Write `checkpoint` as this process's. Another process's checkpoint is
+    /// left alone, whether it's still running or left it to be resumed;
+    /// returns whether `checkpoint` was written.
+    pub fn save(&self, checkpoint: &Checkpoint) -> Result<bool> {
+        let _lock = lock(&self.path)?;
+        // A file that can't be read is no one's and gets replaced
+        if self.load().ok().flatten().is_some_and(|c| !c.is_mine()) {
+            return Ok(false);
+        }
+        self.write(checkpoint)?;
+        Ok(true)
+    }
+
+    /// Make `checkpoint`, as read earlier, this process's, unless it has
+    /// changed since or its writer is still running. Returns whether it's
+    /// now ours.
+    pub fn claim(&self, checkpoint: &Checkpoint) -> Result<bool> {
+        let _lock = lock(&self.path)?;
+        let current = self.load()?;
+        let unchanged = current
+            .as_ref()
+            .is_some_and(|c| c.pid == checkpoint.pid && c.saved_at == checkpoint.saved_at);
+        if !unchanged || checkpoint.is_held_elsewhere() {
+            return Ok(false);
+        }
+        self.write(checkpoint)?;
+        Ok(true)
+    }
+
+    fn write(&self, checkpoint: &Checkpoint) -> Result<()> {
+        let checkpoint = Checkpoint {
+            version: FORMAT_VERSION,
+            pid: std::process::id(),
+            ..checkpoint.clone()
+        };
+        write_atomic(&self.path, &serde_json::to_vec(&checkpoint)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> HistoryStore {
+        let dir = std::env::temp_dir().join(format!("1gh-store-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        HistoryStore::new(dir.join("nested").join(FILE_NAME))
+    }
+
+    fn note(number: u64) -> CompletedNote {
+        CompletedNote {
+            started_at: number as i64 * 10_000,
+            finished_at: number as i64 * 10_000 + 5_000,
+            utc_offset: 0,
+            todos: Vec::new(),
+            time_spent: 600,
+            duration: 3600,
+            overtime: 0,
+            runs: Vec::new(),
+            interruptions: 0,
+            paused_time: 0,
+            adjustments: Vec::new(),
+            completion_number: number,
+        }
+    }
+
+    #[test]
+    fn missing_file_is_empty_history() {
+        let store = temp_store("missing");
+        assert!(store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let store = temp_store("round-trip");
+        store.save(&[], &[note(1), note(2)]).unwrap();
+        let notes = store.load().unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[1].completion_number, 2);
+        let dir = store.path().parent().unwrap();
+        let left: Vec<_> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert!(left.iter().all(|name| !name.to_string_lossy().ends_with(".tmp")));
+    }
+
+    #[test]
+    fn stores_sharing_a_file_keep_each_others_notes() {
+        let first = temp_store("shared");
+        let second = HistoryStore::new(first.path());
+        first.save(&[], &[note(1)]).unwrap();
+        // The second process loaded nothing and completed a session since
+        let merged = second.save(&[], &[note(2)]).unwrap();
+        assert_eq!(merged.notes.len(), 2);
+        assert_eq!(merged.renumbered.get(&2), Some(&2));
+
+        // Saving what was already there again doesn't duplicate it
+        let mine = first.load().unwrap();
+        let merged = first.save(&mine, &[mine.clone(), vec![note(3)]].concat()).unwrap();
+        assert_eq!(merged.notes.len(), 3);
+
+        // Clearing removes only the notes this process knew about
+        second.save(&[], &[note(4)]).unwrap();
+        first.save(&merged.notes, &[]).unwrap();
+        let left = first.load().unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].started_at, note(4).started_at);
+        assert_eq!(left[0].completion_number, 1);
+    }
+
+    #[test]
+    fn notes_finished_in_the_same_instant_are_both_kept() {
+        let store = temp_store("same-instant");
+        let mut first = note(1);
+        first.time_spent = 1;
+        let second = CompletedNote {
+            completion_number: 2,
+            ..note(1)
+        };
+        let merged = store.save(&[], &[first, second]).unwrap();
+        let merged = store.save(&merged.notes, &merged.notes).unwrap();
+        assert_eq!(merged.notes.len(), 2);
+        assert_eq!(merged.notes[0].time_spent, 1);
+    }
+
+    #[test]
+    fn concurrent_saves_are_all_kept() {
+        let store = temp_store("concurrent");
+        let threads: Vec<_> = (1..=8)
+            .map(|n| {
+                let store = store.clone();
+                std::thread::spawn(move || store.save(&[], &[note(n)]).unwrap())
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let notes = store.load().unwrap();
+        assert_eq!(notes.len(), 8);
+        let numbers: Vec<u64> = notes.iter().map(|n| n.completion_number).collect();
+        assert_eq!(numbers, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn merge_moves_carried_tasks_with_their_session() {
+        let mut carried = note(3);
+        carried.completion_number = 2;
+        carried.todos.push(crate::types::Todo {
+            text: "open".to_string(),
+            carried_from: Some(1),
+            ..Default::default()
+        });
+        let mut mine = note(2);
+        mine.completion_number = 1;
+        let merged = merge_notes(&[], &[note(1)], &[mine, carried]);
+        assert_eq!(merged.notes[2].todos[0].carried_from, Some(2));
+        assert_eq!(merged.renumbered, HashMap::from([(1, 2), (2, 3)]));
+    }
+
+    #[test]
+    fn newer_format_is_rejected() {
+        let store = temp_store("newer");
+        fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        fs::write(store.path(), r#"{"version": 99, "notes": []}"#).unwrap();
+        assert!(store.load().is_err());
+    }
+
+    #[test]
+    fn checkpoint_round_trips() {
+        let store = temp_store("checkpoint");
+        let file = RecoveryFile::new(store.path().with_file_name(RECOVERY_FILE_NAME));
+        assert!(file.load().unwrap().is_none());
+        let mut note = ActiveNote::new(1500);
+        note.todos[0].text = "draft".to_string();
+        assert!(file.save(&Checkpoint::new(note, 42)).unwrap());
+        let checkpoint = file.load().unwrap().unwrap();
+        assert_eq!(checkpoint.saved_at, 42);
+        assert_eq!(checkpoint.pid, std::process::id());
+        assert_eq!(checkpoint.note.todos[0].text, "draft");
+    }
+
+    /// A checkpoint written by process `pid`.
+    fn foreign_checkpoint(file: &RecoveryFile, pid: u32) -> Checkpoint {
+        let mut note = ActiveNote::new(1500);
+        note.todos[0].text = "theirs".to_string();
+        let checkpoint = Checkpoint {
+            pid,
+            ..Checkpoint::new(note, 7)
+        };
+        fs::create_dir_all(file.path().parent().unwrap()).unwrap();
+        fs::write(file.path(), serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+        checkpoint
+    }
+
+    /// The pid of a process that has exited.
+    fn exited_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+        child.id()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn live_writers_checkpoint_is_left_alone() {
+        let store = temp_store("live-owner");
+        let file = RecoveryFile::new(store.path().with_file_name(RECOVERY_FILE_NAME));
+        // pid 1 outlives any test
+        let checkpoint = foreign_checkpoint(&file, 1);
+        assert!(checkpoint.is_held_elsewhere());
+        assert!(!file.save(&Checkpoint::new(ActiveNote::new(1500), 8)).unwrap());
+        assert!(!file.claim(&checkpoint).unwrap());
+        assert_eq!(file.load().unwrap().unwrap().note.todos[0].text, "theirs");
+    }
+
+    #[test]
+    fn exited_writers_checkpoint_is_kept_until_claimed() {
+        let store = temp_store("dead-owner");
+        let file = RecoveryFile::new(store.path().with_file_name(RECOVERY_FILE_NAME));
+        let checkpoint = foreign_checkpoint(&file, exited_pid());
+        assert!(!checkpoint.is_held_elsewhere());
+        // Still waiting to be offered for resuming
+        assert!(!file.save(&Checkpoint::new(ActiveNote::new(1500), 8)).unwrap());
+        assert_eq!(file.load().unwrap().unwrap().note.todos[0].text, "theirs");
+
+        assert!(file.claim(&checkpoint).unwrap());
+        assert_eq!(file.load().unwrap().unwrap().pid, std::process::id());
+        assert!(file.save(&Checkpoint::new(ActiveNote::new(1500), 8)).unwrap());
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let store = temp_store("corrupt");
+        fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        fs::write(store.path(), "not json").unwrap();
+        assert!(store.load().is_err());
+    }
+}
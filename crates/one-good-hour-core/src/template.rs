@@ -0,0 +1,499 @@
+//! User-defined export layouts in a small Mustache-like language:
+//!
+//! ```text
+//! {{name}}                 the value of a variable
+//! {{#name}}...{{/name}}    repeat for each item of a list, or show once if
+//!                          the value is true, non-empty and not "0"
+//! {{^name}}...{{/name}}    show if the value is false, empty or "0"
+//! {{! comment }}           ignored
+//! ```
+//!
+//! A section or comment tag alone on its line takes the line with it, so
+//! templates can put one tag per line without leaving blank lines behind.
+//!
+//! Top-level variables: `sessions`, `session_count`, `total_time`,
+//! `tasks_done`, `tasks_total`. Each session has `number`, `date`, `start`,
+//! `end`, `span`, `offset`, `time_spent`, `duration`, `overtime`,
+//! `interruptions`, `paused_time`, `tasks_done`, `tasks_total` and the lists
+//! `todos`, `done` and `open`. Each todo has `text`, `completed`, `check`
+//! ("x" or " "), `time_spent` and `carried_from`. Inside a list, variables of
+//! the enclosing scopes stay visible.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::export::Exporter;
+use crate::store::config_dir;
+use crate::types::{at_offset, format_overtime, format_time, CompletedNote, Todo};
+
+const TEMPLATE_DIR: &str = "templates";
+
+/// `templates` in `config_dir()`.
+pub fn default_dir() -> Option<PathBuf> {
+    config_dir().map(|d| d.join(TEMPLATE_DIR))
+}
+
+/// Every template in `dir`, sorted by name. A missing directory has none.
+pub fn load_dir(dir: &Path) -> Result<Vec<Template>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", dir.display())),
+    };
+    let mut templates = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && !is_hidden(&path) {
+            templates.push(Template::load(&path)?);
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.'))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/// A parsed template. The file name gives both its name and the extension
+/// of exported files: `standup.md` is "standup", saved as `.md`.
+#[derive(Debug, Clone)]
+pub struct Template {
+    name: String,
+    extension: String,
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(name: &str, extension: &str, source: &str) -> Result<Self> {
+        let nodes = parse(source).with_context(|| format!("in template '{name}'"))?;
+        Ok(Self {
+            name: name.to_string(),
+            extension: extension.to_string(),
+            nodes,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("template");
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("txt");
+        Self::parse(name, extension, &source)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Exporter for Template {
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn file_stem(&self) -> &str {
+        &self.name
+    }
+
+    fn render(&self, notes: &[CompletedNote]) -> Result<String> {
+        let root = root_scope(notes);
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![&root], &mut out)
+            .with_context(|| format!("in template '{}'", self.name))?;
+        Ok(out)
+    }
+}
+
+// Parsing
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Var(String),
+    Open(String),
+    Inverted(String),
+    Close(String),
+    Comment,
+}
+
+impl Token {
+    /// Tags that take their whole line when they stand alone on it.
+    fn is_block(&self) -> bool {
+        matches!(
+            self,
+            Token::Open(_) | Token::Inverted(_) | Token::Close(_) | Token::Comment
+        )
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after.find("}}").context("'{{' without a closing '}}'")?;
+        let tag = after[..end].trim();
+        let name = |s: &str| -> Result<String> {
+            let name = s.trim();
+            if name.is_empty() {
+                bail!("empty tag '{{{{{tag}}}}}'");
+            }
+            Ok(name.to_string())
+        };
+        tokens.push(match tag.chars().next() {
+            Some('#') => Token::Open(name(&tag[1..])?),
+            Some('^') => Token::Inverted(name(&tag[1..])?),
+            Some('/') => Token::Close(name(&tag[1..])?),
+            Some('!') => Token::Comment,
+            _ => Token::Var(name(tag)?),
+        });
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Drop the indentation and line break around block tags that are alone on
+/// their line.
+fn strip_standalone(tokens: &mut [Token]) {
+    let last = tokens.len().saturating_sub(1);
+    let standalone: Vec<bool> = (0..tokens.len())
+        .map(|i| {
+            if !tokens[i].is_block() {
+                return false;
+            }
+            let starts_line = i == 0
+                || matches!(&tokens[i - 1], Token::Text(t)
+                    if t.rsplit('\n').next().unwrap_or("").trim().is_empty()
+                        && (t.contains('\n') || i == 1));
+            let ends_line = i == last
+                || matches!(&tokens[i + 1], Token::Text(t)
+                    if t.split('\n').next().unwrap_or("").trim().is_empty()
+                        && (t.contains('\n') || i + 1 == last));
+            starts_line && ends_line
+        })
+        .collect();
+
+    for (i, _) in standalone.iter().enumerate().filter(|(_, s)| **s) {
+        if i > 0 {
+            if let Token::Text(t) = &mut tokens[i - 1] {
+                let keep = t.rfind('\n').map_or(0, |n| n + 1);
+                t.truncate(keep);
+            }
+        }
+        if i < last {
+            if let Token::Text(t) = &mut tokens[i + 1] {
+                let drop = t.find('\n').map_or(t.len(), |n| n + 1);
+                t.replace_range(..drop, "");
+            }
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<Node>> {
+    let mut tokens = tokenize(source)?;
+    strip_standalone(&mut tokens);
+
+    // Open sections: (name, inverted, nodes collected so far)
+    let mut stack: Vec<(String, bool, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+    for token in tokens {
+        let current = stack.last_mut().map_or(&mut nodes, |(_, _, c)| c);
+        match token {
+            Token::Text(t) if !t.is_empty() => current.push(Node::Text(t)),
+            Token::Text(_) | Token::Comment => {}
+            Token::Var(name) => current.push(Node::Var(name)),
+            Token::Open(name) => stack.push((name, false, Vec::new())),
+            Token::Inverted(name) => stack.push((name, true, Vec::new())),
+            Token::Close(name) => {
+                let Some((open, inverted, children)) = stack.pop() else {
+                    bail!("'{{{{/{name}}}}}' closes nothing");
+                };
+                if open != name {
+                    bail!("'{{{{/{name}}}}}' closes '{{{{#{open}}}}}'");
+                }
+                let section = Node::Section {
+                    name,
+                    inverted,
+                    children,
+                };
+                stack.last_mut().map_or(&mut nodes, |(_, _, c)| c).push(section);
+            }
+        }
+    }
+    if let Some((open, _, _)) = stack.pop() {
+        bail!("'{{{{#{open}}}}}' is never closed");
+    }
+    Ok(nodes)
+}
+
+// Rendering
+
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Bool(bool),
+    List(Vec<Scope>),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Text(s) => !s.is_empty() && s != "0",
+            Value::Bool(b) => *b,
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+}
+
+type Scope = BTreeMap<&'static str, Value>;
+
+fn text(s: impl Into<String>) -> Value {
+    Value::Text(s.into())
+}
+
+fn lookup<'a>(scopes: &[&'a Scope], name: &str) -> Result<&'a Value> {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|s| s.get(name))
+        .with_context(|| format!("unknown variable '{name}'"))
+}
+
+fn render_nodes<'a>(nodes: &'a [Node], scopes: &mut Vec<&'a Scope>, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(t) => out.push_str(t),
+            Node::Var(name) => match lookup(scopes, name)? {
+                Value::Text(s) => out.push_str(s),
+                Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+                Value::List(_) => bail!("'{name}' is a list; use {{{{#{name}}}}}...{{{{/{name}}}}}"),
+            },
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let value = lookup(scopes, name)?;
+                if *inverted {
+                    if !value.is_truthy() {
+                        render_nodes(children, scopes, out)?;
+                    }
+                } else if let Value::List(items) = value {
+                    for item in items {
+                        scopes.push(item);
+                        render_nodes(children, scopes, out)?;
+                        scopes.pop();
+                    }
+                } else if value.is_truthy() {
+                    render_nodes(children, scopes, out)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn root_scope(notes: &[CompletedNote]) -> Scope {
+    let tasks = |note: &CompletedNote| note.todos.iter().filter(|t| !t.text.is_empty()).count();
+    let done = |note: &CompletedNote| {
+        note.todos.iter().filter(|t| !t.text.is_empty() && t.completed).count()
+    };
+    Scope::from([
+        ("sessions", Value::List(notes.iter().map(session_scope).collect())),
+        ("session_count", text(notes.len().to_string())),
+        ("total_time", text(format_time(notes.iter().map(|n| n.time_spent).sum()))),
+        ("tasks_done", text(notes.iter().map(done).sum::<usize>().to_string())),
+        ("tasks_total", text(notes.iter().map(tasks).sum::<usize>().to_string())),
+    ])
+}
+
+fn session_scope(note: &CompletedNote) -> Scope {
+    // Notes saved before timestamps were recorded have no times
+    let time = |ms: i64, fmt: &str| {
+        if note.finished_at > 0 {
+            at_offset(ms, note.utc_offset).format(fmt).to_string()
+        } else {
+            String::new()
+        }
+    };
+    let todos: Vec<&Todo> = note.todos.iter().filter(|t| !t.text.is_empty()).collect();
+    let list = |keep: fn(&Todo) -> bool| {
+        Value::List(todos.iter().filter(|t| keep(t)).map(|t| todo_scope(t)).collect())
+    };
+    let done = todos.iter().filter(|t| t.completed).count();
+    Scope::from([
+        ("number", text(note.completion_number.to_string())),
+        ("date", text(time(note.started_at, "%Y-%m-%d"))),
+        ("start", text(time(note.started_at, "%H:%M"))),
+        ("end", text(time(note.finished_at, "%H:%M"))),
+        ("offset", text(time(note.finished_at, "%:z"))),
+        ("span", text(note.span().unwrap_or_default())),
+        ("time_spent", text(format_time(note.time_spent))),
+        ("duration", text(format_time(note.duration))),
+        (
+            "overtime",
+            text(if note.overtime > 0 { format_overtime(note.overtime) } else { String::new() }),
+        ),
+        ("interruptions", text(note.interruptions.to_string())),
+        ("paused_time", text(format_time(note.paused_time))),
+        ("tasks_done", text(done.to_string())),
+        ("tasks_total", text(todos.len().to_string())),
+        ("todos", list(|_| true)),
+        ("done", list(|t| t.completed)),
+        ("open", list(|t| !t.completed)),
+    ])
+}
+
+fn todo_scope(todo: &Todo) -> Scope {
+    let time_spent = if todo.time_spent > 0 { format_time(todo.time_spent) } else { String::new() };
+    Scope::from([
+        ("text", text(todo.text.clone())),
+        ("completed", Value::Bool(todo.completed)),
+        ("check", text(if todo.completed { "x" } else { " " })),
+        ("time_spent", text(time_spent)),
+        (
+            "carried_from",
+            text(todo.carried_from.map(|n| n.to_string()).unwrap_or_default()),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-17 12:03 UTC.
+    const START_MS: i64 = 1_792_238_580_000;
+
+    fn notes() -> Vec<CompletedNote> {
+        let todo = |text: &str, completed: bool| Todo {
+            text: text.to_string(),
+            completed,
+            ..Todo::default()
+        };
+        let note = |number: u64, todos: Vec<Todo>| CompletedNote {
+            started_at: START_MS,
+            finished_at: START_MS + 1_800_000,
+            utc_offset: 7200,
+            todos,
+            time_spent: 1800,
+            duration: 3600,
+            overtime: 0,
+            runs: Vec::new(),
+            interruptions: 0,
+            paused_time: 0,
+            adjustments: Vec::new(),
+            completion_number: number,
+        };
+        vec![
+            note(1, vec![todo("write", true), todo("review", false), Todo::default()]),
+            note(2, vec![todo("ship", true)]),
+        ]
+    }
+
+    fn render(source: &str) -> String {
+        Template::parse("test", "md", source)
+            .unwrap()
+            .render(&notes())
+            .unwrap()
+    }
+
+    #[test]
+    fn loops_over_sessions_and_todos() {
+        let out = render(
+            "{{#sessions}}\n\
+             ## {{date}} {{start}}-{{end}}\n\
+             {{#todos}}\n\
+             - [{{check}}] {{text}}\n\
+             {{/todos}}\n\
+             {{/sessions}}\n",
+        );
+        assert_eq!(
+            out,
+            "## 2026-10-17 14:03-14:33\n- [x] write\n- [ ] review\n\
+             ## 2026-10-17 14:03-14:33\n- [x] ship\n"
+        );
+    }
+
+    #[test]
+    fn sections_on_values_and_outer_scope() {
+        let out = render(
+            "{{#sessions}}{{#open}}{{number}}: {{text}}{{/open}}{{^open}}{{number}} clear{{/open}};{{/sessions}}",
+        );
+        assert_eq!(out, "1: review;2 clear;");
+    }
+
+    #[test]
+    fn totals_and_comments() {
+        let out = render("{{! standup }}\n{{tasks_done}}/{{tasks_total}} in {{total_time}}");
+        assert_eq!(out, "2/3 in 60:00");
+    }
+
+    #[test]
+    fn total_tasks_done_matches_sessions() {
+        let mut notes = notes();
+        // A checked row whose text was cleared afterwards
+        notes[1].todos.push(Todo {
+            completed: true,
+            ..Todo::default()
+        });
+        let source = "{{tasks_done}}={{#sessions}}+{{tasks_done}}{{/sessions}}";
+        let template = Template::parse("t", "md", source).unwrap();
+        assert_eq!(template.render(&notes).unwrap(), "2=+1+1");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Template::parse("t", "md", "{{#sessions}}").is_err());
+        assert!(Template::parse("t", "md", "{{#a}}{{/b}}").is_err());
+        assert!(Template::parse("t", "md", "{{/a}}").is_err());
+        assert!(Template::parse("t", "md", "{{name").is_err());
+        assert!(Template::parse("t", "md", "{{ }}").is_err());
+    }
+
+    #[test]
+    fn unknown_variable_is_a_render_error() {
+        let template = Template::parse("t", "md", "{{nope}}").unwrap();
+        let err = template.render(&notes()).unwrap_err();
+        assert!(format!("{err:#}").contains("unknown variable 'nope'"));
+    }
+
+    #[test]
+    fn load_dir_names_templates_by_file() {
+        let dir = std::env::temp_dir().join(format!("1gh-templates-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(load_dir(&dir).unwrap().is_empty());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("standup.txt"), "{{session_count}}").unwrap();
+        fs::write(dir.join("journal.md"), "{{total_time}}").unwrap();
+        fs::write(dir.join(".journal.md.swp"), "{{").unwrap();
+        let templates = load_dir(&dir).unwrap();
+        let names: Vec<&str> = templates.iter().map(|t| t.name()).collect();
+        assert_eq!(names, ["journal", "standup"]);
+        assert_eq!(templates[1].extension(), "txt");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,17 @@
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
-pub const MAX_TIME: u64 = 3600;
+/// Planned session length used when nothing else is configured.
+pub const DEFAULT_DURATION: u64 = 3600;
+
+/// Empty task rows a new note starts with.
+pub const DEFAULT_TODO_SLOTS: usize = 4;
+
+/// Upper bound for the configurable task cap.
+pub const MAX_TODO_CAP: usize = 8;
+
+/// Session lengths offered when cycling through presets (25, 45, 60 and 90 minutes).
+pub const DURATION_PRESETS: [u64; 4] = [25 * 60, 45 * 60, 60 * 60, 90 * 60];
 
 pub fn format_time(seconds: u64) -> String {
     let mins = seconds / 60;
@@ -8,6 +19,51 @@ pub fn format_time(seconds: u64) -> String {
     format!("{mins}:{secs:02}")
 }
 
+/// Time past the planned duration, shown as "+12:34".
+pub fn format_overtime(seconds: u64) -> String {
+    format!("+{}", format_time(seconds))
+}
+
+/// A time adjustment, shown as "+5:00" or "-5:00".
+pub fn format_adjustment(seconds: i64) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    format!("{sign}{}", format_time(seconds.unsigned_abs()))
+}
+
+/// Seconds east of UTC of the local time zone at `ms` (Unix milliseconds).
+pub fn local_offset_at(ms: i64) -> i32 {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map_or(0, |t| t.offset().local_minus_utc())
+}
+
+/// `ms` (Unix milliseconds) as a date-time at `offset` seconds east of UTC.
+pub fn at_offset(ms: i64, offset: i32) -> DateTime<FixedOffset> {
+    let offset = FixedOffset::east_opt(offset).unwrap_or(FixedOffset::east_opt(0).unwrap());
+    DateTime::<Utc>::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .with_timezone(&offset)
+}
+
+/// "2026-10-17 14:03–14:58 +02:00", with the end date repeated if the
+/// span crosses midnight.
+pub fn format_span(start_ms: i64, end_ms: i64, offset: i32) -> String {
+    let start = at_offset(start_ms, offset);
+    let end = at_offset(end_ms, offset);
+    let end_fmt = if start.date_naive() == end.date_naive() {
+        "%H:%M"
+    } else {
+        "%Y-%m-%d %H:%M"
+    };
+    format!(
+        "{}–{} {}",
+        start.format("%Y-%m-%d %H:%M"),
+        end.format(end_fmt),
+        start.format("%:z")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,8 +90,107 @@ mod tests {
     }
 
     #[test]
-    fn format_time_max() {
-        assert_eq!(format_time(MAX_TIME), "60:00");
+    fn format_time_default_duration() {
+        assert_eq!(format_time(DEFAULT_DURATION), "60:00");
+    }
+
+    #[test]
+    fn format_time_over_an_hour() {
+        assert_eq!(format_time(90 * 60), "90:00");
+    }
+
+    #[test]
+    fn format_overtime_has_plus_sign() {
+        assert_eq!(format_overtime(754), "+12:34");
+    }
+
+    #[test]
+    fn format_span_same_day() {
+        // 2026-10-17 12:03 UTC, shown at UTC+2
+        let start = 1_792_238_580_000;
+        let end = start + 55 * 60_000;
+        assert_eq!(format_span(start, end, 7200), "2026-10-17 14:03–14:58 +02:00");
+    }
+
+    #[test]
+    fn format_span_across_midnight() {
+        let start = 1_792_238_580_000;
+        let end = start + 10 * 3_600_000;
+        assert_eq!(format_span(start, end, 0), "2026-10-17 12:03–22:03 +00:00");
+        assert_eq!(format_span(start, end, 7200), "2026-10-17 14:03–2026-10-18 00:03 +02:00");
+    }
+
+    #[test]
+    fn pause_stats_counts_gaps_between_runs() {
+        let runs = [
+            RunInterval { started_at: 0, ended_at: Some(10_000) },
+            RunInterval { started_at: 70_000, ended_at: Some(80_000) },
+            RunInterval { started_at: 95_500, ended_at: None },
+        ];
+        assert_eq!(pause_stats(&runs), (2, 75));
+        assert_eq!(pause_stats(&runs[..1]), (0, 0));
+        assert_eq!(pause_stats(&[]), (0, 0));
+    }
+
+    #[test]
+    fn end_run_closes_only_open_run() {
+        let mut note = ActiveNote::default();
+        note.end_run(5);
+        assert!(note.runs.is_empty());
+        note.start_run(1_000);
+        note.end_run(4_000);
+        note.end_run(9_000);
+        assert_eq!(note.runs[0].ended_at, Some(4_000));
+    }
+
+    #[test]
+    fn active_todo_follows_row_changes() {
+        let mut note = ActiveNote {
+            active_todo: Some(1),
+            ..ActiveNote::default()
+        };
+        note.insert_todo(0, Todo::default());
+        assert_eq!(note.active_todo, Some(2));
+        note.swap_todos(2, 3);
+        assert_eq!(note.active_todo, Some(3));
+        note.remove_todo(0);
+        assert_eq!(note.active_todo, Some(2));
+        note.remove_todo(2);
+        assert_eq!(note.active_todo, None);
+    }
+
+    #[test]
+    fn credit_goes_to_active_todo_only() {
+        let mut note = ActiveNote::default();
+        note.credit_active_todo(30);
+        assert!(note.todos.iter().all(|t| t.time_spent == 0));
+        note.active_todo = Some(2);
+        note.credit_active_todo(30);
+        note.credit_active_todo(15);
+        assert_eq!(note.todos[2].time_spent, 45);
+    }
+
+    #[test]
+    fn active_note_progress() {
+        let mut note = ActiveNote::new(25 * 60);
+        assert_eq!(note.progress(), 0.0);
+        note.time_left = 15 * 60;
+        assert_eq!(note.elapsed(), 10 * 60);
+        assert!((note.progress() - 0.4).abs() < f64::EPSILON);
+        note.time_left = 0;
+        assert_eq!(note.progress(), 1.0);
+    }
+
+    #[test]
+    fn note_saved_by_an_older_version_loads() {
+        let json = r#"{"todos":[{"text":"old","completed":true}],"time_spent":1200,"completion_number":3}"#;
+        let note: CompletedNote = serde_json::from_str(json).unwrap();
+        assert_eq!(note.todos[0].text, "old");
+        assert_eq!(note.todos[0].time_spent, 0);
+        assert_eq!(note.time_spent, 1200);
+        assert_eq!(note.duration, 0);
+        assert!(note.runs.is_empty() && note.adjustments.is_empty());
+        assert_eq!(note.span(), None);
     }
 
     #[test]
@@ -49,30 +204,201 @@ mod tests {
 pub struct Todo {
     pub text: String,
     pub completed: bool,
+    /// Focused seconds spent while this was the active task.
+    #[serde(default)]
+    pub time_spent: u64,
+    /// Unix milliseconds when the task was checked off.
+    pub completed_at: Option<i64>,
+    /// Completion number of the session this task was carried over from.
+    pub carried_from: Option<u64>,
+}
+
+/// A stretch of time the focus timer was running, in Unix milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunInterval {
+    pub started_at: i64,
+    /// `None` while the timer is still running.
+    pub ended_at: Option<i64>,
+}
+
+/// Time added to (positive) or removed from (negative) a session while it ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeAdjustment {
+    /// Unix milliseconds when the change was made.
+    pub at: i64,
+    pub seconds: i64,
+}
+
+/// Count the pauses between consecutive runs and their total length in seconds.
+/// A pause before the first run or after the last one is not an interruption.
+pub fn pause_stats(runs: &[RunInterval]) -> (u32, u64) {
+    let mut count = 0;
+    let mut paused_ms = 0;
+    for pair in runs.windows(2) {
+        if let Some(end) = pair[0].ended_at {
+            count += 1;
+            paused_ms += (pair[1].started_at - end).max(0) as u64;
+        }
+    }
+    (count, paused_ms / 1000)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletedNote {
+    /// Unix milliseconds (UTC) when the timer first started, or when the
+    /// note was completed if it never ran.
+    #[serde(default)]
+    pub started_at: i64,
+    /// Unix milliseconds (UTC) when the note was completed.
+    #[serde(default)]
+    pub finished_at: i64,
+    /// Seconds east of UTC of the local time zone when the note was completed.
+    #[serde(default)]
+    pub utc_offset: i32,
     pub todos: Vec<Todo>,
     pub time_spent: u64,
+    /// Planned length in seconds; 0 in notes saved before it was recorded.
+    #[serde(default)]
+    pub duration: u64,
+    /// Seconds worked past `duration` in overtime mode.
+    #[serde(default)]
+    pub overtime: u64,
+    #[serde(default)]
+    pub runs: Vec<RunInterval>,
+    /// Times the session was paused and resumed.
+    #[serde(default)]
+    pub interruptions: u32,
+    /// Seconds spent paused between runs.
+    #[serde(default)]
+    pub paused_time: u64,
+    #[serde(default)]
+    pub adjustments: Vec<TimeAdjustment>,
     pub completion_number: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActiveNote {
-    pub todos: [Todo; 4],
+    pub todos: Vec<Todo>,
+    pub duration: u64,
     pub time_left: u64,
+    /// Seconds counted past zero in overtime mode.
+    pub overtime: u64,
     pub is_running: bool,
     pub target_time: Option<i64>,
+    pub runs: Vec<RunInterval>,
+    pub adjustments: Vec<TimeAdjustment>,
+    /// Index of the task being worked on; focused time is credited to it.
+    pub active_todo: Option<usize>,
 }
 
-impl Default for ActiveNote {
-    fn default() -> Self {
+impl CompletedNote {
+    /// When the session happened, in the time zone it happened in. `None`
+    /// for notes saved before timestamps were recorded.
+    pub fn span(&self) -> Option<String> {
+        (self.finished_at > 0)
+            .then(|| format_span(self.started_at, self.finished_at, self.utc_offset))
+    }
+}
+
+impl ActiveNote {
+    pub fn new(duration: u64) -> Self {
+        Self::with_slots(duration, DEFAULT_TODO_SLOTS)
+    }
+
+    /// A note with `slots` empty task rows (at least one).
+    pub fn with_slots(duration: u64, slots: usize) -> Self {
         Self {
-            todos: std::array::from_fn(|_| Todo::default()),
-            time_left: MAX_TIME,
+            todos: vec![Todo::default(); slots.max(1)],
+            duration,
+            time_left: duration,
+            overtime: 0,
             is_running: false,
             target_time: None,
+            runs: Vec::new(),
+            adjustments: Vec::new(),
+            active_todo: None,
+        }
+    }
+
+    /// Insert a task row, keeping `active_todo` on the same task.
+    pub fn insert_todo(&mut self, index: usize, todo: Todo) {
+        self.todos.insert(index, todo);
+        if let Some(active) = self.active_todo.as_mut().filter(|a| **a >= index) {
+            *active += 1;
+        }
+    }
+
+    /// Remove a task row, keeping `active_todo` on the same task (or
+    /// clearing it if that task was removed).
+    pub fn remove_todo(&mut self, index: usize) -> Todo {
+        self.active_todo = match self.active_todo {
+            Some(a) if a == index => None,
+            Some(a) if a > index => Some(a - 1),
+            other => other,
+        };
+        self.todos.remove(index)
+    }
+
+    /// Swap two task rows, keeping `active_todo` on the same task.
+    pub fn swap_todos(&mut self, a: usize, b: usize) {
+        self.todos.swap(a, b);
+        self.active_todo = self.active_todo.map(|i| match i {
+            i if i == a => b,
+            i if i == b => a,
+            i => i,
+        });
+    }
+
+    /// Nothing typed and no time on the clock: not worth recovering.
+    pub fn is_blank(&self) -> bool {
+        self.todos.iter().all(|t| t.text.is_empty())
+            && self.runs.is_empty()
+            && self.elapsed() == 0
+            && self.overtime == 0
+    }
+
+    /// Tasks with text that are not checked off yet.
+    pub fn unfinished_todos(&self) -> impl Iterator<Item = &Todo> {
+        self.todos.iter().filter(|t| !t.text.is_empty() && !t.completed)
+    }
+
+    /// Add focused seconds to the active task.
+    pub fn credit_active_todo(&mut self, seconds: u64) {
+        if let Some(todo) = self.active_todo.and_then(|i| self.todos.get_mut(i)) {
+            todo.time_spent += seconds;
+        }
+    }
+
+    pub fn start_run(&mut self, now_ms: i64) {
+        self.runs.push(RunInterval {
+            started_at: now_ms,
+            ended_at: None,
+        });
+    }
+
+    /// Close the open run, if any.
+    pub fn end_run(&mut self, now_ms: i64) {
+        if let Some(run) = self.runs.last_mut().filter(|r| r.ended_at.is_none()) {
+            run.ended_at = Some(now_ms.max(run.started_at));
+        }
+    }
+
+    /// Seconds of the planned duration already used.
+    pub fn elapsed(&self) -> u64 {
+        self.duration.saturating_sub(self.time_left)
+    }
+
+    /// Fraction of the planned duration already used, in [0.0, 1.0].
+    pub fn progress(&self) -> f64 {
+        if self.duration == 0 {
+            return 1.0;
         }
+        (self.elapsed() as f64 / self.duration as f64).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for ActiveNote {
+    fn default() -> Self {
+        Self::new(DEFAULT_DURATION)
     }
 }
@@ -0,0 +1,149 @@
+use crate::cycle::Break;
+use crate::types::{ActiveNote, CompletedNote, Todo};
+
+/// Most commands kept on the undo stack.
+const UNDO_LIMIT: usize = 100;
+
+/// A user-visible change to the notes that can be undone and redone.
+/// Only user actions are recorded; timer ticks never are.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// A task row changed in place (edited, checked, cleared).
+    SetTodo {
+        label: &'static str,
+        index: usize,
+        before: Todo,
+        after: Todo,
+        /// `active_todo` before and after; checking or clearing the task
+        /// being worked on stops crediting it.
+        active_before: Option<usize>,
+        active_after: Option<usize>,
+    },
+    InsertTodo { index: usize, todo: Todo },
+    DeleteTodo { index: usize, todo: Todo },
+    SwapTodos { a: usize, b: usize },
+    /// The session as a whole was replaced: completed, started over, or
+    /// its history cleared.
+    ReplaceSession {
+        label: &'static str,
+        before: Box<SessionState>,
+        after: Box<SessionState>,
+    },
+}
+
+impl Command {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Command::SetTodo { label, .. } | Command::ReplaceSession { label, .. } => label,
+            Command::InsertTodo { .. } => "add task",
+            Command::DeleteTodo { .. } => "delete task",
+            Command::SwapTodos { .. } => "move task",
+        }
+    }
+}
+
+/// Everything a session-level command swaps out.
+#[derive(Debug, Clone)]
+pub struct SessionState {
+    pub active_note: ActiveNote,
+    pub completed_notes: Vec<CompletedNote>,
+    pub history_index: Option<usize>,
+    pub on_break: Option<Break>,
+    pub focus_blocks: u32,
+    pub selected_todo: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+}
+
+impl UndoStack {
+    /// Record a new command. Anything that could be redone is dropped.
+    pub fn push(&mut self, command: Command) {
+        self.redo.clear();
+        self.undo.push(command);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// The most recent command, for merging follow-up changes into it.
+    pub fn last_mut(&mut self) -> Option<&mut Command> {
+        self.undo.last_mut()
+    }
+
+    /// Take the command to undo; it moves to the redo stack.
+    pub fn undo(&mut self) -> Option<Command> {
+        let command = self.undo.pop()?;
+        self.redo.push(command.clone());
+        Some(command)
+    }
+
+    /// Take the command to redo; it moves back to the undo stack.
+    pub fn redo(&mut self) -> Option<Command> {
+        let command = self.redo.pop()?;
+        self.undo.push(command.clone());
+        Some(command)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap(a: usize) -> Command {
+        Command::SwapTodos { a, b: a + 1 }
+    }
+
+    #[test]
+    fn undo_then_redo_round_trips() {
+        let mut stack = UndoStack::default();
+        stack.push(swap(0));
+        stack.push(swap(1));
+        assert!(matches!(stack.undo(), Some(Command::SwapTodos { a: 1, .. })));
+        assert!(stack.can_redo());
+        assert!(matches!(stack.redo(), Some(Command::SwapTodos { a: 1, .. })));
+        assert!(!stack.can_redo());
+        assert!(matches!(stack.undo(), Some(Command::SwapTodos { a: 1, .. })));
+    }
+
+    #[test]
+    fn push_clears_redo() {
+        let mut stack = UndoStack::default();
+        stack.push(swap(0));
+        stack.undo();
+        stack.push(swap(2));
+        assert!(!stack.can_redo());
+        assert!(stack.redo().is_none());
+    }
+
+    #[test]
+    fn stack_is_capped() {
+        let mut stack = UndoStack::default();
+        for i in 0..UNDO_LIMIT + 5 {
+            stack.push(swap(i));
+        }
+        let mut count = 0;
+        while stack.undo().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, UNDO_LIMIT);
+    }
+
+    #[test]
+    fn empty_stack_has_nothing() {
+        let mut stack = UndoStack::default();
+        assert!(!stack.can_undo());
+        assert!(stack.undo().is_none());
+    }
+}
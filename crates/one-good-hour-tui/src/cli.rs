@@ -0,0 +1,437 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use one_good_hour_core::clock::GapPolicy;
+use one_good_hour_core::control::Action;
+use one_good_hour_core::cycle::CycleConfig;
+use one_good_hour_core::export::Format;
+use one_good_hour_core::milestone::Milestone;
+use one_good_hour_core::types::MAX_TODO_CAP;
+
+pub const USAGE: &str = "\
+Usage: 1gh [options]
+       1gh start [-d <minutes>] | pause | reset
+       1gh add <task> | check <number>
+       1gh complete [--carry | --no-carry] [--save-history]
+       1gh status [--json]
+       1gh daemon [options] | daemon stop
+       1gh export [--format md|json|csv|ics | --template <name>] [-o <file>]
+
+Options:
+  -d, --duration <minutes>  Planned session length (default 60)
+      --short-break <minutes>
+                            Short break length, 0 to disable (default 5)
+      --long-break <minutes>
+                            Long break length (default 15)
+      --long-break-every <n>
+                            Take a long break after every n focus blocks (default 4)
+      --auto-start          Start the next phase automatically
+      --overtime            Keep counting past zero instead of stopping
+      --on-sleep <policy>   After the computer sleeps mid-session:
+                            pause, count or ask (default ask)
+      --max-tasks <n>       Most tasks per session, 1 to 8 (default 4)
+      --carry-over          Move unfinished tasks into the next session
+      --save-history        Save completed sessions to disk and load them
+                            on start (stays on while the history file exists)
+      --no-save-history     Don't load or save history this run
+      --no-recovery         Don't keep a copy of the current session on disk
+                            for resuming after a crash
+      --template <name>     Copy history with this template (see below)
+      --milestone <spec>    Alert at half, at=<minutes> or left=<minutes>;
+                            repeatable (default: half and left=5)
+      --no-milestones       Turn off milestone alerts
+  -h, --help                Print this help
+
+The session commands run without the TUI, on the same session it checkpoints
+(see --no-recovery): start a timer from a script and it keeps running until
+pause, or until the TUI picks it up. The session keeps its break and the
+options it was started with from one command to the next. Tasks are numbered
+from 1 as status lists them. complete adds the session to the saved history.
+While a TUI has the session open they refuse; status only reads it.
+
+While a daemon runs, it owns the session: 1gh attaches to it instead of
+keeping its own, so every terminal shows the same live timer, and the session
+commands go to it too. Give the daemon the options above; the TUI refuses
+them while attached.
+
+Export writes the saved history (see --save-history) to stdout, or to
+<file> in the format its extension names (default Markdown).
+
+Templates are files in $XDG_CONFIG_HOME/one-good-hour/templates (by default
+~/.config/one-good-hour/templates), named after the file: standup.md is
+\"standup\". Press T to pick one for copying, or e to export with one.";
+
+/// What the binary was asked to do.
+pub enum Command {
+    Run(Options),
+    Export(ExportOptions),
+    /// A session command: start, pause, reset, add, check or complete.
+    Control(ControlOptions),
+    Status { json: bool },
+    /// Serve the session to other `1gh` processes.
+    Daemon(Options),
+    StopDaemon,
+    Help,
+}
+
+/// Settings for the session commands.
+pub struct ControlOptions {
+    pub action: Action,
+    /// Start saving history, for `complete`.
+    pub save_history: bool,
+}
+
+/// Settings for `1gh export`.
+#[derive(Default)]
+pub struct ExportOptions {
+    /// `None` goes by the output file's extension, then Markdown.
+    pub format: Option<Format>,
+    /// A user template to render with instead of `format`.
+    pub template: Option<String>,
+    /// `None` writes to stdout.
+    pub output: Option<PathBuf>,
+}
+
+/// Settings for the interactive TUI or the daemon.
+#[derive(Default)]
+pub struct Options {
+    /// Planned session length in seconds.
+    pub session_length: Option<u64>,
+    pub cycle: CycleConfig,
+    pub overtime: bool,
+    pub gap_policy: GapPolicy,
+    pub todo_cap: Option<usize>,
+    pub carry_over: bool,
+    /// `None` saves only if a history file already exists.
+    pub save_history: Option<bool>,
+    pub no_recovery: bool,
+    /// `None` keeps the default milestones.
+    pub milestones: Option<Vec<Milestone>>,
+    /// Template to copy with instead of Markdown.
+    pub template: Option<String>,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter().peekable();
+    if args.next_if(|a| a == "export").is_some() {
+        return parse_export(args);
+    }
+    if args.next_if(|a| a == "status").is_some() {
+        return match args.next().as_deref() {
+            None => Ok(Command::Status { json: false }),
+            Some("--json") => Ok(Command::Status { json: true }),
+            Some("-h" | "--help") => Ok(Command::Help),
+            Some(other) => bail!("unknown status argument '{other}'\n\n{USAGE}"),
+        };
+    }
+    if let Some(name) = args.next_if(|a| CONTROL_COMMANDS.contains(&a.as_str())) {
+        return parse_control(&name, args);
+    }
+
+    let daemon = args.next_if(|a| a == "daemon").is_some();
+    if daemon && args.next_if(|a| a == "stop").is_some() {
+        return Ok(Command::StopDaemon);
+    }
+
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg, None),
+        };
+        let mut value = || match inline.clone() {
+            Some(v) => Ok(v),
+            None => args.next().with_context(|| format!("{flag} needs a value")),
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-d" | "--duration" => options.session_length = Some(parse_minutes(&value()?)?),
+            "--short-break" => options.cycle.short_break = parse_break(&value()?)?,
+            "--long-break" => options.cycle.long_break = parse_break(&value()?)?,
+            "--long-break-every" => {
+                let value = value()?;
+                options.cycle.long_break_every = value
+                    .parse()
+                    .with_context(|| format!("invalid block count '{value}'"))?;
+            }
+            "--auto-start" => options.cycle.auto_start = true,
+            "--overtime" => options.overtime = true,
+            "--carry-over" => options.carry_over = true,
+            "--save-history" => options.save_history = Some(true),
+            "--no-save-history" => options.save_history = Some(false),
+            "--no-recovery" => options.no_recovery = true,
+            "--template" => options.template = Some(value()?),
+            "--max-tasks" => {
+                let value = value()?;
+                let cap: usize = value
+                    .parse()
+                    .with_context(|| format!("invalid task count '{value}'"))?;
+                if !(1..=MAX_TODO_CAP).contains(&cap) {
+                    bail!("--max-tasks must be between 1 and {MAX_TODO_CAP}");
+                }
+                options.todo_cap = Some(cap);
+            }
+            "--milestone" => {
+                let milestone = value()?.parse::<Milestone>().map_err(anyhow::Error::msg)?;
+                options.milestones.get_or_insert_with(Vec::new).push(milestone);
+            }
+            "--no-milestones" => options.milestones = Some(Vec::new()),
+            "--on-sleep" => {
+                options.gap_policy = match value()?.as_str() {
+                    "pause" => GapPolicy::Pause,
+                    "count" => GapPolicy::Count,
+                    "ask" => GapPolicy::Ask,
+                    other => bail!("invalid --on-sleep policy '{other}', expected pause, count or ask"),
+                }
+            }
+            other => bail!("unknown argument '{other}'\n\n{USAGE}"),
+        }
+    }
+
+    Ok(if daemon {
+        Command::Daemon(options)
+    } else {
+        Command::Run(options)
+    })
+}
+
+fn parse_export(mut args: impl Iterator<Item = String>) -> Result<Command> {
+    let mut options = ExportOptions::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg, None),
+        };
+        let mut value = || match inline.clone() {
+            Some(v) => Ok(v),
+            None => args.next().with_context(|| format!("{flag} needs a value")),
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-f" | "--format" => {
+                options.format = Some(value()?.parse::<Format>().map_err(anyhow::Error::msg)?)
+            }
+            "-t" | "--template" => options.template = Some(value()?),
+            "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+            other => bail!("unknown export argument '{other}'\n\n{USAGE}"),
+        }
+    }
+    if options.format.is_some() && options.template.is_some() {
+        bail!("--format and --template can't be used together");
+    }
+    Ok(Command::Export(options))
+}
+
+const CONTROL_COMMANDS: [&str; 6] = ["start", "pause", "reset", "add", "check", "complete"];
+
+fn parse_control(name: &str, args: impl Iterator<Item = String>) -> Result<Command> {
+    let args: Vec<String> = args.collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        return Ok(Command::Help);
+    }
+    let mut save_history = false;
+    let no_args = |action| {
+        if let Some(arg) = args.first() {
+            bail!("unknown {name} argument '{arg}'\n\n{USAGE}");
+        }
+        Ok(action)
+    };
+    let action = match name {
+        "start" => Action::Start {
+            session_length: match args.as_slice() {
+                [] => None,
+                [flag, minutes] if flag == "-d" || flag == "--duration" => {
+                    Some(parse_minutes(minutes)?)
+                }
+                [flag] if flag == "-d" || flag == "--duration" => bail!("{flag} needs a value"),
+                [arg, ..] => bail!("unknown start argument '{arg}'\n\n{USAGE}"),
+            },
+        },
+        "pause" => no_args(Action::Pause)?,
+        "reset" => no_args(Action::Reset)?,
+        "add" => {
+            if args.is_empty() {
+                bail!("add needs the task text");
+            }
+            Action::Add { text: args.join(" ") }
+        }
+        "check" => match args.as_slice() {
+            [number] => Action::Check {
+                number: number
+                    .parse()
+                    .with_context(|| format!("invalid task number '{number}'"))?,
+            },
+            _ => bail!("check needs one task number"),
+        },
+        _ => {
+            let mut carry = None;
+            for arg in &args {
+                match arg.as_str() {
+                    "--carry" => carry = Some(true),
+                    "--no-carry" => carry = Some(false),
+                    "--save-history" => save_history = true,
+                    other => bail!("unknown complete argument '{other}'\n\n{USAGE}"),
+                }
+            }
+            Action::Complete { carry }
+        }
+    };
+    Ok(Command::Control(ControlOptions { action, save_history }))
+}
+
+fn parse_minutes(value: &str) -> Result<u64> {
+    let minutes: u64 = value
+        .parse()
+        .with_context(|| format!("invalid duration '{value}', expected whole minutes"))?;
+    if minutes == 0 || minutes > 24 * 60 {
+        bail!("duration must be between 1 and 1440 minutes");
+    }
+    Ok(minutes * 60)
+}
+
+/// Like `parse_minutes`, but 0 turns the break off.
+fn parse_break(value: &str) -> Result<u64> {
+    let minutes: u64 = value
+        .parse()
+        .with_context(|| format!("invalid break length '{value}', expected whole minutes"))?;
+    if minutes > 24 * 60 {
+        bail!("break length must be between 1 and 1440 minutes, or 0 for none");
+    }
+    Ok(minutes * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Command> {
+        parse(line.split_whitespace().map(String::from))
+    }
+
+    fn error(line: &str) -> String {
+        match parse_line(line) {
+            Ok(_) => panic!("'{line}' parsed"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn action(line: &str) -> Action {
+        match parse_line(line) {
+            Ok(Command::Control(options)) => options.action,
+            _ => panic!("'{line}' is not a session command"),
+        }
+    }
+
+    fn options(line: &str) -> Options {
+        match parse_line(line) {
+            Ok(Command::Run(options)) => options,
+            _ => panic!("'{line}' doesn't run the TUI"),
+        }
+    }
+
+    #[test]
+    fn run_options() {
+        let parsed = options("-d 25 --short-break=0 --long-break 20 --overtime --max-tasks 6");
+        assert_eq!(parsed.session_length, Some(1500));
+        assert_eq!(parsed.cycle.short_break, 0);
+        assert_eq!(parsed.cycle.long_break, 1200);
+        assert!(parsed.overtime);
+        assert_eq!(parsed.todo_cap, Some(6));
+        assert!(options("").session_length.is_none());
+    }
+
+    #[test]
+    fn run_option_errors() {
+        assert!(error("--bogus").starts_with("unknown argument '--bogus'"));
+        assert_eq!(error("-d"), "-d needs a value");
+        assert!(error("-d 0").contains("between 1 and 1440"));
+        assert!(error("-d 1441").contains("between 1 and 1440"));
+        assert!(error("-d soon").starts_with("invalid duration"));
+        assert!(error("--max-tasks 99").contains("between 1 and"));
+        assert!(error("--on-sleep never").starts_with("invalid --on-sleep policy"));
+        assert!(error("--milestone at=x").starts_with("invalid milestone"));
+    }
+
+    #[test]
+    fn break_length_is_bounded() {
+        assert!(error("--short-break 1441").contains("between 1 and 1440"));
+        assert!(error("--long-break 18446744073709551615").contains("between 1 and 1440"));
+        assert!(error("--long-break x").starts_with("invalid break length"));
+        assert_eq!(options("--long-break 1440").cycle.long_break, 1440 * 60);
+    }
+
+    #[test]
+    fn start() {
+        assert_eq!(action("start"), Action::Start { session_length: None });
+        assert_eq!(action("start -d 25"), Action::Start { session_length: Some(1500) });
+        assert_eq!(action("start --duration 90"), Action::Start { session_length: Some(5400) });
+        assert_eq!(error("start -d"), "-d needs a value");
+        assert!(error("start -d 2000").contains("between 1 and 1440"));
+        assert!(error("start now").starts_with("unknown start argument 'now'"));
+    }
+
+    #[test]
+    fn pause_and_reset_take_no_arguments() {
+        assert_eq!(action("pause"), Action::Pause);
+        assert_eq!(action("reset"), Action::Reset);
+        assert!(error("pause now").starts_with("unknown pause argument 'now'"));
+        assert!(error("reset all").starts_with("unknown reset argument 'all'"));
+    }
+
+    #[test]
+    fn add_joins_its_words() {
+        let text = "Write the report".to_string();
+        assert_eq!(action("add Write the report"), Action::Add { text });
+        assert_eq!(error("add"), "add needs the task text");
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(action("check 2"), Action::Check { number: 2 });
+        assert_eq!(error("check"), "check needs one task number");
+        assert_eq!(error("check 1 2"), "check needs one task number");
+        assert!(error("check two").starts_with("invalid task number 'two'"));
+    }
+
+    #[test]
+    fn complete() {
+        assert_eq!(action("complete"), Action::Complete { carry: None });
+        assert_eq!(action("complete --carry"), Action::Complete { carry: Some(true) });
+        assert_eq!(action("complete --no-carry"), Action::Complete { carry: Some(false) });
+        match parse_line("complete --save-history") {
+            Ok(Command::Control(options)) => assert!(options.save_history),
+            _ => panic!("not a session command"),
+        }
+        assert!(error("complete --keep").starts_with("unknown complete argument '--keep'"));
+    }
+
+    #[test]
+    fn status_and_daemon() {
+        assert!(matches!(parse_line("status"), Ok(Command::Status { json: false })));
+        assert!(matches!(parse_line("status --json"), Ok(Command::Status { json: true })));
+        assert!(error("status --yaml").starts_with("unknown status argument '--yaml'"));
+        assert!(matches!(parse_line("daemon --overtime"), Ok(Command::Daemon(o)) if o.overtime));
+        assert!(matches!(parse_line("daemon stop"), Ok(Command::StopDaemon)));
+        assert!(error("daemon --bogus").starts_with("unknown argument"));
+    }
+
+    #[test]
+    fn help_anywhere() {
+        for line in ["-h", "--help", "start --help", "add -h", "status -h", "export --help"] {
+            assert!(matches!(parse_line(line), Ok(Command::Help)), "{line}");
+        }
+    }
+
+    #[test]
+    fn export() {
+        let Ok(Command::Export(options)) = parse_line("export -f csv -o out.csv") else {
+            panic!("not an export");
+        };
+        assert_eq!(options.format, Some(Format::Csv));
+        assert_eq!(options.output, Some(PathBuf::from("out.csv")));
+        assert!(error("export -f md -t standup").contains("can't be used together"));
+        assert!(error("export --since monday").starts_with("unknown export argument"));
+    }
+}
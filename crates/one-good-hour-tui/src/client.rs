@@ -0,0 +1,78 @@
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+
+use anyhow::{bail, Result};
+use crossterm::event::{self as terminal_event, Event};
+
+use one_good_hour_core::app::App;
+
+use crate::event::{handle_local, TICK_RATE};
+use crate::protocol::{send, Connection, Reply, Request};
+
+enum Message {
+    Input(Event),
+    Reply(Reply),
+    Closed(String),
+}
+
+/// The TUI attached to a daemon: input goes to the daemon, and the app only
+/// mirrors the views it sends back.
+pub struct Attached {
+    sender: UnixStream,
+    messages: Receiver<Message>,
+}
+
+impl Attached {
+    pub fn new(mut connection: Connection) -> Result<Self> {
+        connection.send(&Request::Subscribe)?;
+        let sender = connection.sender()?;
+        let (tx, messages) = mpsc::channel();
+
+        let replies = tx.clone();
+        thread::spawn(move || loop {
+            let message = match connection.receive() {
+                Ok(reply) => Message::Reply(reply),
+                Err(e) => Message::Closed(format!("{e:#}")),
+            };
+            let closed = matches!(message, Message::Closed(_));
+            if replies.send(message).is_err() || closed {
+                return;
+            }
+        });
+        thread::spawn(move || {
+            while let Ok(event) = terminal_event::read() {
+                if tx.send(Message::Input(event)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Self { sender, messages })
+    }
+
+    /// Forward this terminal's input and show what the daemon sends back,
+    /// waiting up to a tick for either.
+    pub fn step(&mut self, app: &mut App) -> Result<()> {
+        let mut next = self.messages.recv_timeout(TICK_RATE);
+        loop {
+            match next {
+                Ok(Message::Input(event)) => {
+                    if !handle_local(app, &event) {
+                        send(&mut self.sender, &Request::Event { event })?;
+                    }
+                }
+                Ok(Message::Reply(Reply::View { view })) => app.show_view(*view),
+                Ok(Message::Reply(_)) => {}
+                Ok(Message::Closed(e)) => bail!("lost the daemon: {e}"),
+                Err(RecvTimeoutError::Timeout) => return Ok(()),
+                Err(RecvTimeoutError::Disconnected) => bail!("lost the daemon"),
+            }
+            // Catch up on whatever else arrived before drawing
+            next = self.messages.try_recv().map_err(|e| match e {
+                TryRecvError::Empty => RecvTimeoutError::Timeout,
+                TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+            });
+        }
+    }
+}
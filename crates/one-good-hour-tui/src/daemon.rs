@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use one_good_hour_core::app::App;
+use one_good_hour_core::control::{self, Status};
+
+use crate::event::{handle_event, TICK_RATE};
+use crate::protocol::{send, Reply, Request};
+
+/// A client that doesn't read its replies for this long is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum Incoming {
+    Connected(usize, UnixStream),
+    Request(usize, Request),
+    Invalid(usize, String),
+    Closed(usize),
+}
+
+struct Client {
+    stream: UnixStream,
+    subscribed: bool,
+}
+
+/// Own `app` and serve it on a socket at `path` until asked to shut down.
+/// Every client sees the same session; the timer ticks here whether or not
+/// any are attached.
+pub fn serve(mut app: App, path: &Path) -> Result<()> {
+    let listener = bind(path)?;
+    eprintln!("Listening on {}", path.display());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || accept(listener, tx));
+
+    let mut clients: HashMap<usize, Client> = HashMap::new();
+    let mut last_view = String::new();
+    let mut last_history = history_key(&app);
+    loop {
+        match rx.recv_timeout(TICK_RATE) {
+            Ok(Incoming::Connected(id, stream)) => {
+                clients.insert(
+                    id,
+                    Client {
+                        stream,
+                        subscribed: false,
+                    },
+                );
+            }
+            Ok(Incoming::Closed(id)) => {
+                clients.remove(&id);
+            }
+            Ok(Incoming::Invalid(id, error)) => reply(&mut clients, id, &Reply::Error { error }),
+            Ok(Incoming::Request(id, Request::Shutdown)) => {
+                let message = "Daemon stopped".to_string();
+                reply(&mut clients, id, &Reply::Done { message });
+                break;
+            }
+            Ok(Incoming::Request(id, request)) => handle(&mut app, &mut clients, id, request),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => bail!("stopped accepting connections"),
+        }
+        app.tick();
+        // Quitting is up to each client
+        app.should_quit = false;
+
+        // Send subscribers the session whenever it changed, with the
+        // history only when that did
+        let history = history_key(&app);
+        let view = serde_json::to_string(&app.view(false))?;
+        if view != last_view || history != last_history {
+            let update = Reply::View {
+                view: Box::new(app.view(history != last_history)),
+            };
+            let subscribers: Vec<usize> = clients
+                .iter()
+                .filter(|(_, c)| c.subscribed)
+                .map(|(&id, _)| id)
+                .collect();
+            for id in subscribers {
+                reply(&mut clients, id, &update);
+            }
+            // The alerts are handed on
+            app.sound_pending = false;
+            app.milestone_pending = None;
+            last_view = serde_json::to_string(&app.view(false))?;
+            last_history = history;
+        }
+    }
+
+    app.tick();
+    let _ = fs::remove_file(path);
+    Ok(())
+}
+
+fn handle(app: &mut App, clients: &mut HashMap<usize, Client>, id: usize, request: Request) {
+    match request {
+        Request::Action { action } => {
+            let answer = match control::apply(app, &action) {
+                Ok(message) => Reply::Done { message },
+                Err(e) => Reply::Error {
+                    error: format!("{e:#}"),
+                },
+            };
+            reply(clients, id, &answer);
+        }
+        Request::Status => {
+            let status = Status::of(app);
+            reply(clients, id, &Reply::Status { status });
+        }
+        Request::Subscribe => {
+            let view = Box::new(app.view(true));
+            reply(clients, id, &Reply::View { view });
+            if let Some(client) = clients.get_mut(&id) {
+                client.subscribed = true;
+            }
+        }
+        Request::Event { event } => handle_event(app, event),
+        Request::Shutdown => unreachable!("handled by serve"),
+    }
+}
+
+/// Send `message` to client `id`, dropping the client if it can't take it.
+fn reply(clients: &mut HashMap<usize, Client>, id: usize, message: &Reply) {
+    let Some(client) = clients.get_mut(&id) else {
+        return;
+    };
+    if send(&mut client.stream, message).is_err() {
+        clients.remove(&id);
+    }
+}
+
+/// Completed notes are only ever added or removed, so their count and the
+/// last one's end tell whether the history changed.
+fn history_key(app: &App) -> (usize, Option<i64>) {
+    (
+        app.completed_notes.len(),
+        app.completed_notes.last().map(|n| n.finished_at),
+    )
+}
+
+fn bind(path: &Path) -> Result<UnixListener> {
+    if UnixStream::connect(path).is_ok() {
+        bail!("a daemon is already running on {}", path.display());
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    let _ = fs::remove_file(path);
+    if let Some(dir) = path.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("creating {}", dir.display()))?;
+    }
+    // Only this user may drive the session, so the socket is created
+    // private rather than made so after.
+    // SAFETY: umask only swaps the process's file mode mask, and no other
+    // thread is running yet to create files under it.
+    let mask = unsafe { libc::umask(0o177) };
+    let bound = UnixListener::bind(path);
+    unsafe { libc::umask(mask) };
+    bound.with_context(|| format!("listening on {}", path.display()))
+}
+
+/// Take connections, reading each one's requests on a thread of its own.
+fn accept(listener: UnixListener, tx: Sender<Incoming>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        let _ = writer.set_write_timeout(Some(WRITE_TIMEOUT));
+        if tx.send(Incoming::Connected(id, writer)).is_err() {
+            return;
+        }
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let incoming = match serde_json::from_str(&line) {
+                    Ok(request) => Incoming::Request(id, request),
+                    Err(e) => Incoming::Invalid(id, format!("invalid request: {e}")),
+                };
+                if tx.send(incoming).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(Incoming::Closed(id));
+        });
+    }
+}
@@ -2,16 +2,26 @@ use std::time::Duration;
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 
-use one_good_hour_core::app::{App, InputMode};
+use one_good_hour_core::app::{App, InputMode, ModalKind};
+use one_good_hour_core::export::Format;
 
-const TICK_RATE: Duration = Duration::from_millis(250);
+pub const TICK_RATE: Duration = Duration::from_millis(250);
 
 pub fn handle_events(app: &mut App) -> anyhow::Result<()> {
     if event::poll(TICK_RATE)? {
-        if let Event::Key(key) = event::read()? {
+        handle_event(app, event::read()?);
+    }
+    // Always tick the timer
+    app.tick();
+    Ok(())
+}
+
+pub fn handle_event(app: &mut App, event: Event) {
+    match event {
+        Event::Key(key) => {
             // Ignore key release events (crossterm on Windows sends both press and release)
             if key.kind != event::KeyEventKind::Press {
-                return Ok(());
+                return;
             }
             match &app.input_mode {
                 InputMode::Normal => handle_normal(app, key),
@@ -19,13 +29,54 @@ pub fn handle_events(app: &mut App) -> anyhow::Result<()> {
                 InputMode::Modal => handle_modal(app, key),
             }
         }
+        Event::Paste(text) => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_str(&text, idx);
+            }
+        }
+        _ => {}
     }
-    // Always tick the timer
-    app.tick();
-    Ok(())
+}
+
+/// Handle what only concerns this terminal when attached to a daemon:
+/// quitting, the layout toggles, events that aren't input, and copying and
+/// exporting, which use this terminal's clipboard, directory and templates
+/// on the history the daemon sent. Returns `false` for events the daemon
+/// should handle.
+pub fn handle_local(app: &mut App, event: &Event) -> bool {
+    let key = match event {
+        Event::Key(key) if key.kind == event::KeyEventKind::Press => *key,
+        Event::Paste(_) => return false,
+        _ => return true,
+    };
+    if matches!(app.modal, Some(ModalKind::Export)) {
+        handle_modal(app, key);
+        return true;
+    }
+    if !matches!(app.input_mode, InputMode::Normal) {
+        return false;
+    }
+    app.status_message = None;
+    match key.code {
+        KeyCode::Char('q') => app.should_quit = true,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.should_quit = true
+        }
+        KeyCode::Char('H') => app.toggle_history(),
+        KeyCode::Char('M') => app.toggle_heatmap(),
+        KeyCode::Char('y') => app.copy_markdown(),
+        KeyCode::Char('Y') => app.copy_current_note(),
+        KeyCode::Char('e') => app.show_export_modal(),
+        KeyCode::Char('T') => app.cycle_copy_template(),
+        _ => return false,
+    }
+    true
 }
 
 fn handle_normal(app: &mut App, key: KeyEvent) {
+    // Clear status message on any keypress
+    app.status_message = None;
+
     match key.code {
         // Quit
         KeyCode::Char('q') => app.should_quit = true,
@@ -33,9 +84,22 @@ fn handle_normal(app: &mut App, key: KeyEvent) {
             app.should_quit = true
         }
 
+        // Undo
+        KeyCode::Char('u') => app.undo(),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+
         // Timer
         KeyCode::Char(' ') => app.toggle_timer(),
         KeyCode::Char('r') => app.reset_timer(),
+        KeyCode::Char('s') => app.cycle_session_length(),
+        KeyCode::Char('+') | KeyCode::Char('=') => app.extend_session(),
+        KeyCode::Char('-') => app.shorten_session(),
+        KeyCode::Char('b') => app.skip_break(),
+        KeyCode::Char('A') => app.toggle_auto_start(),
+        KeyCode::Char('o') => app.toggle_overtime(),
+        KeyCode::Char('C') => app.toggle_carry_over(),
+        KeyCode::Char('P') => app.start_saving_history(),
+        KeyCode::Char('g') => app.cycle_gap_policy(),
 
         // Todo navigation
         KeyCode::Char('j') | KeyCode::Down => app.move_selection_down(),
@@ -45,6 +109,12 @@ fn handle_normal(app: &mut App, key: KeyEvent) {
         KeyCode::Enter => app.start_editing(),
         KeyCode::Char('x') => app.toggle_todo(),
         KeyCode::Char('d') => app.remove_todo(),
+        KeyCode::Char('a') => app.add_todo(),
+        KeyCode::Char('i') => app.insert_todo(),
+        KeyCode::Char('X') | KeyCode::Delete => app.delete_todo(),
+        KeyCode::Char('J') => app.move_todo_down(),
+        KeyCode::Char('K') => app.move_todo_up(),
+        KeyCode::Char('w') => app.toggle_active_todo(),
 
         // Session
         KeyCode::Char('c') => app.show_complete_session_modal(),
@@ -54,25 +124,44 @@ fn handle_normal(app: &mut App, key: KeyEvent) {
 
         // Clipboard
         KeyCode::Char('y') => app.copy_markdown(),
+        KeyCode::Char('Y') => app.copy_current_note(),
+        KeyCode::Char('e') => app.show_export_modal(),
+        KeyCode::Char('T') => app.cycle_copy_template(),
         KeyCode::Char('D') => app.show_clear_notes_modal(),
         KeyCode::Char('N') => app.show_new_session_modal(),
 
         // Toggle history
         KeyCode::Char('H') => app.toggle_history(),
+        KeyCode::Char('S') => app.show_stats(),
+        KeyCode::Char('M') => app.toggle_heatmap(),
 
         // Help
         KeyCode::Char('?') => app.show_help(),
 
         _ => {}
     }
-    // Clear status message on any keypress
-    app.status_message = None;
 }
 
 fn handle_editing(app: &mut App, key: KeyEvent, index: usize) {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+            KeyCode::Char('w') | KeyCode::Backspace => app.edit_delete_word(index),
+            KeyCode::Char('u') => app.edit_delete_to_start(index),
+            KeyCode::Char('a') => app.edit_cursor_home(index),
+            KeyCode::Char('e') => app.edit_cursor_end(index),
+            _ => {}
+        }
+        return;
+    }
+
     match key.code {
         KeyCode::Enter | KeyCode::Esc => app.stop_editing(),
         KeyCode::Backspace => app.edit_backspace(index),
+        KeyCode::Delete => app.edit_delete(index),
+        KeyCode::Left => app.edit_cursor_left(index),
+        KeyCode::Right => app.edit_cursor_right(index),
+        KeyCode::Home => app.edit_cursor_home(index),
+        KeyCode::End => app.edit_cursor_end(index),
         KeyCode::Char(c) => app.edit_char(c, index),
         _ => {}
     }
@@ -81,6 +170,13 @@ fn handle_editing(app: &mut App, key: KeyEvent, index: usize) {
 fn handle_modal(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Char('y') | KeyCode::Enter => app.confirm_modal(),
+        KeyCode::Char('k') => app.confirm_complete_session(true),
+        KeyCode::Char('f') => app.confirm_complete_session(false),
+        KeyCode::Char('m') => app.confirm_export(Format::Markdown),
+        KeyCode::Char('j') => app.confirm_export(Format::Json),
+        KeyCode::Char('c') => app.confirm_export(Format::Csv),
+        KeyCode::Char('i') => app.confirm_export(Format::Ical),
+        KeyCode::Char(c @ '1'..='9') => app.confirm_export_template(c as usize - '1' as usize),
         KeyCode::Char('n') | KeyCode::Esc => app.dismiss_modal(),
         _ => {}
     }
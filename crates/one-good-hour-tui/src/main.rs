@@ -1,49 +1,331 @@
+mod cli;
+#[cfg(unix)]
+mod client;
+#[cfg(unix)]
+mod daemon;
 mod event;
+#[cfg(unix)]
+mod protocol;
 mod ui;
 
 use std::io;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
     execute,
     terminal::{self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use one_good_hour_core::app::App;
+use one_good_hour_core::control::{self, Action, Status};
+use one_good_hour_core::export::{self, Exporter, Format};
+use one_good_hour_core::template::{self, Template};
+use one_good_hour_core::store::{HistoryStore, RecoveryFile};
 
 fn main() -> Result<()> {
-    let mut app = App::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    #[cfg(unix)]
+    let has_options = !args.is_empty();
+    let options = match cli::parse(args)? {
+        cli::Command::Run(options) => options,
+        cli::Command::Export(options) => return export_history(options),
+        cli::Command::Control(options) => return control(options),
+        cli::Command::Status { json } => return print_status(json),
+        cli::Command::Daemon(options) => return run_daemon(options),
+        cli::Command::StopDaemon => return stop_daemon(),
+        cli::Command::Help => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+    };
+
+    #[cfg(unix)]
+    if let Some(daemon) = protocol::Connection::open()? {
+        if has_options {
+            bail!(
+                "a daemon is running the session, so these options would do nothing here; \
+                 give them to `1gh daemon`, or stop it with `1gh daemon stop`"
+            );
+        }
+        return run_attached(daemon);
+    }
+
+    let mut app = build_app(options)?;
+    run_terminal(&mut app, &mut event::handle_events)
+}
+
+/// The app `options` describe, with history, templates and crash recovery.
+fn build_app(options: cli::Options) -> Result<App> {
+    let mut app = match options.session_length {
+        Some(seconds) => App::with_session_length(seconds),
+        None => App::new(),
+    };
+    app.cycle = options.cycle;
+    app.allow_overtime = options.overtime;
+    app.gap_policy = options.gap_policy;
+    app.carry_over = options.carry_over;
+    if let Some(cap) = options.todo_cap {
+        app.set_todo_cap(cap);
+    }
+    if let Some(milestones) = options.milestones {
+        app.milestones = milestones;
+    }
+    let store = match options.save_history {
+        Some(true) => HistoryStore::open_default(),
+        Some(false) => None,
+        None => HistoryStore::existing(),
+    };
+    if let Some(store) = store {
+        app = app.with_store(store);
+    }
+    if let Ok(dir) = std::env::current_dir() {
+        app.export_dir = dir;
+    }
+    match load_templates() {
+        Ok(templates) => app.templates = templates,
+        Err(e) => app.status_message = Some(format!("Template error: {e:#}")),
+    }
+    if let Some(name) = &options.template {
+        if !app.select_copy_template(name) {
+            bail!("no template named '{name}'");
+        }
+    }
+    if !options.no_recovery {
+        if let Some(file) = RecoveryFile::open_default() {
+            app = app.with_recovery(file);
+        }
+    }
+    Ok(app)
+}
 
+/// Run the TUI on `app`, with `step` handling input between frames.
+fn run_terminal(app: &mut App, step: &mut dyn FnMut(&mut App) -> Result<()>) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Run app loop
-    let result = run(&mut terminal, &mut app);
+    let result = run(&mut terminal, app, step);
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
     result
 }
 
-/// Height of fixed UI sections (title + timer + todos + action bar)
-const COMPACT_HEIGHT: u16 = 13;
+/// `1gh` while a daemon runs: show its session and send it the input.
+#[cfg(unix)]
+fn run_attached(daemon: protocol::Connection) -> Result<()> {
+    let mut app = App::new();
+    // Copying and exporting happen here, not in the daemon
+    app.templates = load_templates().unwrap_or_default();
+    if let Ok(dir) = std::env::current_dir() {
+        app.export_dir = dir;
+    }
+    let mut attached = client::Attached::new(daemon)?;
+    run_terminal(&mut app, &mut |app| attached.step(app))
+}
+
+/// `1gh daemon`: own the session and serve it to other `1gh` processes.
+#[cfg(unix)]
+fn run_daemon(options: cli::Options) -> Result<()> {
+    let path = protocol::socket_path().context("no runtime or data directory for the socket")?;
+    let mut app = build_app(options)?;
+    if let Some(message) = &app.status_message {
+        eprintln!("{message}");
+    }
+    // No one is attached yet to answer, and the session isn't checkpointed
+    // while the question is open
+    if matches!(app.modal, Some(one_good_hour_core::app::ModalKind::Resume { .. })) {
+        app.confirm_modal();
+        eprintln!("Resumed the unfinished session");
+    }
+    daemon::serve(app, &path)
+}
+
+#[cfg(not(unix))]
+fn run_daemon(_options: cli::Options) -> Result<()> {
+    bail!("the daemon needs Unix domain sockets")
+}
+
+/// `1gh daemon stop`.
+#[cfg(unix)]
+fn stop_daemon() -> Result<()> {
+    let mut daemon = protocol::Connection::open()?.context("no daemon is running")?;
+    let request = protocol::Request::Shutdown;
+    if let protocol::Reply::Done { message } = ask_daemon(&mut daemon, &request)? {
+        println!("{message}");
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn stop_daemon() -> Result<()> {
+    bail!("the daemon needs Unix domain sockets")
+}
+
+/// Send `request` and wait for the answer, turning an error reply into an
+/// error.
+#[cfg(unix)]
+fn ask_daemon(
+    daemon: &mut protocol::Connection,
+    request: &protocol::Request,
+) -> Result<protocol::Reply> {
+    match daemon.request(request)? {
+        protocol::Reply::Error { error } => bail!(error),
+        reply => Ok(reply),
+    }
+}
 
-fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+/// `1gh export`: write the saved history without starting the TUI.
+fn export_history(options: cli::ExportOptions) -> Result<()> {
+    let store = HistoryStore::existing()
+        .context("no saved history; turn saving on with --save-history or P")?;
+    let notes = store.load()?;
+    if notes.is_empty() {
+        bail!("{} has no sessions to export", store.path().display());
+    }
+    let templates;
+    let exporter: &dyn Exporter = match &options.template {
+        Some(name) => {
+            templates = load_templates()?;
+            templates
+                .iter()
+                .find(|t| t.name() == name)
+                .with_context(|| format!("no template named '{name}'"))?
+        }
+        None => options
+            .format
+            .or_else(|| options.output.as_deref().and_then(Format::from_path))
+            .unwrap_or(Format::Markdown)
+            .exporter(),
+    };
+    match options.output {
+        Some(path) => {
+            export::write_file(exporter, &notes, &path)?;
+            eprintln!("Saved to {}", path.display());
+        }
+        None => print!("{}", exporter.render(&notes)?),
+    }
+    Ok(())
+}
+
+/// The session as the last command or TUI run left it, for the session
+/// commands.
+fn headless_app(save_history: bool) -> Result<App> {
+    let file = RecoveryFile::open_default().context("no data directory to keep the session in")?;
+    let store = if save_history {
+        HistoryStore::open_default()
+    } else {
+        HistoryStore::existing()
+    };
+    let mut app = App::new();
+    if let Some(store) = store {
+        app = app.with_store(store);
+    }
+    if let Some(message) = app.status_message.take() {
+        bail!(message);
+    }
+    app.with_session(file)
+}
+
+/// `1gh start`, `add` and the other session commands, sent to the daemon
+/// if one is running.
+fn control(options: cli::ControlOptions) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(mut daemon) = protocol::Connection::open()? {
+        let request = protocol::Request::Action { action: options.action };
+        if let protocol::Reply::Done { message } = ask_daemon(&mut daemon, &request)? {
+            println!("{message}");
+        }
+        return Ok(());
+    }
+
+    let mut app = headless_app(options.save_history)?;
+    let message = control::apply(&mut app, &options.action)?;
+    // Checkpoint the change
+    app.tick();
+    println!("{message}");
+    if matches!(options.action, Action::Complete { .. }) && !app.is_saving_history() {
+        eprintln!("History isn't being saved, so this session is gone; pass --save-history to keep the next one");
+    }
+    if let Some(message) = app.status_message.take() {
+        eprintln!("{message}");
+    }
+    Ok(())
+}
+
+/// `1gh status`: where the session stands, as text or JSON.
+fn print_status(json: bool) -> Result<()> {
+    let status = status()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        println!("{status}");
+    }
+    Ok(())
+}
+
+/// The daemon's session if one is running, otherwise the checkpointed one.
+fn status() -> Result<Status> {
+    #[cfg(unix)]
+    if let Some(mut daemon) = protocol::Connection::open()? {
+        return match ask_daemon(&mut daemon, &protocol::Request::Status)? {
+            protocol::Reply::Status { status } => Ok(status),
+            _ => bail!("unexpected reply from the daemon"),
+        };
+    }
+    // Read only: nothing is ticked on or written back
+    let file = RecoveryFile::open_default().context("no data directory to keep the session in")?;
+    let mut app = App::new();
+    if let Some(store) = HistoryStore::existing() {
+        app = app.with_store(store);
+    }
+    Ok(Status::of(&app.peek_session(&file)?))
+}
+
+/// The user's templates, if the config directory has any.
+fn load_templates() -> Result<Vec<Template>> {
+    match template::default_dir() {
+        Some(dir) => template::load_dir(&dir),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Height of fixed UI sections (title + timer + todos + action bar + heatmap)
+fn compact_height(app: &App) -> u16 {
+    // title 2 + timer 2 + bordered todo rows + action bar 3
+    let heatmap = if app.show_heatmap { ui::HEATMAP_HEIGHT } else { 0 };
+    app.active_note.todos.len() as u16 + 9 + heatmap
+}
+
+/// How long the status line flashes after a milestone.
+const FLASH_DURATION: Duration = Duration::from_secs(2);
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    step: &mut dyn FnMut(&mut App) -> Result<()>,
+) -> Result<()> {
     let mut was_showing_history = app.show_history;
+    let mut was_todo_count = app.active_note.todos.len();
+    let mut was_showing_heatmap = app.show_heatmap;
     let mut saved_height: Option<u16> = None;
+    let mut flash_until: Option<Instant> = None;
 
     loop {
-        terminal.draw(|frame| ui::draw(frame, app))?;
+        let flashing = flash_until.is_some_and(|t| Instant::now() < t);
+        terminal.draw(|frame| ui::draw(frame, app, flashing))?;
 
-        event::handle_events(app)?;
+        step(app)?;
 
         // Resize terminal window when history visibility changes
         if app.show_history != was_showing_history {
@@ -54,16 +336,29 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                 }
             } else {
                 saved_height = Some(rows);
-                resize_window(COMPACT_HEIGHT, cols);
+                resize_window(compact_height(app), cols);
             }
             was_showing_history = app.show_history;
+        } else if !app.show_history
+            && (app.active_note.todos.len() != was_todo_count || app.show_heatmap != was_showing_heatmap)
+        {
+            // Keep the compact window fitted to the task list and heatmap
+            let (cols, _) = terminal::size()?;
+            resize_window(compact_height(app), cols);
         }
+        was_todo_count = app.active_note.todos.len();
+        was_showing_heatmap = app.show_heatmap;
 
         if app.sound_pending {
             print!("\x07");
             app.sound_pending = false;
         }
 
+        if app.milestone_pending.take().is_some() {
+            print!("\x07");
+            flash_until = Some(Instant::now() + FLASH_DURATION);
+        }
+
         if app.should_quit {
             // Restore terminal size if history is hidden on quit
             if let Some(h) = saved_height {
@@ -0,0 +1,117 @@
+//! What `1gh daemon` and its clients say to each other over the daemon's
+//! Unix socket: one JSON object per line, requests from the client and
+//! replies from the daemon, each tagged with a `type`.
+//!
+//! ```text
+//! → {"type":"action","action":{"action":"add","text":"Write the report"}}
+//! ← {"type":"done","message":"Added task 1: Write the report"}
+//! → {"type":"status"}
+//! ← {"type":"status","status":{"phase":"Focus","running":false,...}}
+//! ```
+
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::Event;
+use serde::{Deserialize, Serialize};
+
+use one_good_hour_core::control::{Action, SessionView, Status};
+use one_good_hour_core::store::data_dir;
+
+const SOCKET_NAME: &str = "one-good-hour.sock";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Apply a session command; answered with `Done` or `Error`.
+    Action { action: Action },
+    /// Answered with `Status`.
+    Status,
+    /// Send a `View` now and again after every change until the connection
+    /// closes.
+    Subscribe,
+    /// Input from an attached TUI, handled as if typed into the daemon.
+    Event { event: Event },
+    /// Stop the daemon; answered with `Done`.
+    Shutdown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reply {
+    Done { message: String },
+    Error { error: String },
+    Status { status: Status },
+    View { view: Box<SessionView> },
+}
+
+/// `$XDG_RUNTIME_DIR/one-good-hour.sock`, or the same name in `data_dir()`
+/// when there's no runtime directory.
+pub fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(data_dir)
+        .map(|dir| dir.join(SOCKET_NAME))
+}
+
+/// Write `message` as one line.
+pub fn send(stream: &mut impl Write, message: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// A client's connection to the daemon.
+pub struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Connection {
+    /// Connect to the running daemon; `None` if none is listening.
+    pub fn open() -> Result<Option<Self>> {
+        let Some(path) = socket_path() else {
+            return Ok(None);
+        };
+        let stream = match UnixStream::connect(&path) {
+            Ok(stream) => stream,
+            // No socket, or one left behind by a daemon that didn't exit cleanly
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e).with_context(|| format!("connecting to {}", path.display())),
+        };
+        Ok(Some(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        }))
+    }
+
+    pub fn send(&mut self, request: &Request) -> Result<()> {
+        send(&mut self.writer, request).context("the daemon closed the connection")
+    }
+
+    /// Wait for the next reply.
+    pub fn receive(&mut self) -> Result<Reply> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("the daemon closed the connection");
+        }
+        serde_json::from_str(&line).context("unreadable reply from the daemon")
+    }
+
+    pub fn request(&mut self, request: &Request) -> Result<Reply> {
+        self.send(request)?;
+        self.receive()
+    }
+
+    /// A handle for sending while another thread waits on replies.
+    pub fn sender(&self) -> Result<UnixStream> {
+        Ok(self.writer.try_clone()?)
+    }
+}
@@ -0,0 +1,95 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use one_good_hour_core::app::App;
+use one_good_hour_core::stats::{format_minutes, GOOD_HOUR};
+
+use super::colors;
+
+/// Height of the heatmap panel: month labels, seven days and the legend,
+/// plus borders.
+pub(crate) const HEATMAP_HEIGHT: u16 = 11;
+
+/// Weeks in a year, rounded up so the oldest column reaches back a full year.
+const MAX_WEEKS: u16 = 53;
+
+/// Width of the weekday labels on the left.
+const GUTTER: u16 = 4;
+
+const CELL: &str = "■";
+
+/// Focus per day over the past year, one column per week, shaded from faint
+/// (nothing) to bright (the busiest day, or a good hour if none reached it).
+pub(super) fn draw_heatmap(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors::normal()));
+    let inner = block.inner(area);
+
+    // Leave a gap between cells when the whole year fits that way
+    let cell_width = if inner.width >= GUTTER + MAX_WEEKS * 2 { 2 } else { 1 };
+    let weeks = (inner.width.saturating_sub(GUTTER) / cell_width).min(MAX_WEEKS);
+    let calendar = app.stats().calendar(app.today(), weeks as u64);
+
+    let days = calendar.iter().flatten().flatten();
+    let total: u64 = days.clone().map(|d| d.seconds).sum();
+    let scale = days.map(|d| d.seconds).max().unwrap_or(0).max(GOOD_HOUR);
+
+    let title = format!(" Focus · {} in {} weeks ", format_minutes(total), calendar.len());
+    frame.render_widget(block.title(title), area);
+
+    let label = Style::default().fg(colors::dim());
+    let shade = |seconds: u64| {
+        if seconds == 0 {
+            colors::faint()
+        } else {
+            colors::gradient_color(1.0 - (seconds as f64 / scale as f64).min(1.0))
+        }
+    };
+    let cell = |color| {
+        Span::styled(format!("{CELL:<w$}", w = cell_width as usize), Style::default().fg(color))
+    };
+
+    // Month names over the first week that starts in each month, skipping
+    // the oldest column so a partial month isn't labelled
+    let mut months = " ".repeat(GUTTER as usize);
+    let mut last_month = None;
+    for (i, week) in calendar.iter().enumerate() {
+        let Some(first) = week[0] else { continue };
+        let month = first.date.format("%b").to_string();
+        let column = GUTTER as usize + i * cell_width as usize;
+        if last_month.as_ref().is_some_and(|m| *m != month) && months.chars().count() <= column {
+            months.push_str(&" ".repeat(column - months.chars().count()));
+            months.push_str(&month);
+        }
+        last_month = Some(month);
+    }
+    let mut lines = vec![Line::from(Span::styled(months, label))];
+
+    for (row, name) in ["Mon", "", "Wed", "", "Fri", "", ""].iter().enumerate() {
+        let mut spans = vec![Span::styled(format!("{name:<w$}", w = GUTTER as usize), label)];
+        spans.extend(calendar.iter().map(|week| match week[row] {
+            Some(day) => cell(shade(day.seconds)),
+            None => Span::raw(" ".repeat(cell_width as usize)),
+        }));
+        lines.push(Line::from(spans));
+    }
+
+    let mut legend = vec![Span::styled(format!("{}Less ", " ".repeat(GUTTER as usize)), label)];
+    legend.extend([0, scale / 4, scale / 2, scale * 3 / 4, scale].map(|s| cell(shade(s))));
+    legend.push(Span::styled(if cell_width == 1 { " More" } else { "More" }, label));
+    if !app.is_saving_history() {
+        legend.push(Span::styled(
+            "   [P] Save history to fill this in over time",
+            Style::default().fg(colors::faint()),
+        ));
+    }
+    lines.push(Line::from(legend));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -1,7 +1,9 @@
 pub(crate) mod colors;
 mod gradient_bar;
+mod heatmap;
 mod modal;
 mod sections;
+mod stats;
 
 use ratatui::{
     layout::{Constraint, Layout},
@@ -10,7 +12,10 @@ use ratatui::{
 
 use one_good_hour_core::app::App;
 
-pub fn draw(frame: &mut Frame, app: &App) {
+pub(crate) use heatmap::HEATMAP_HEIGHT;
+
+/// Draw the whole UI. `flashing` highlights the status line after a milestone.
+pub fn draw(frame: &mut Frame, app: &App, flashing: bool) {
     let area = frame.area();
 
     let history_constraint = if app.show_history {
@@ -19,11 +24,14 @@ pub fn draw(frame: &mut Frame, app: &App) {
         Constraint::Length(0)
     };
 
+    let heatmap_height = if app.show_heatmap { HEATMAP_HEIGHT } else { 0 };
+
     let chunks = Layout::vertical([
         Constraint::Length(2),  // Title
         Constraint::Length(2),  // Timer/progress bar + hint
-        Constraint::Length(6),  // Todos
+        Constraint::Length(app.active_note.todos.len() as u16 + 2), // Todos
         Constraint::Length(3),  // Action bar
+        Constraint::Length(heatmap_height), // Heatmap
         history_constraint,     // History
     ])
     .split(area);
@@ -31,12 +39,15 @@ pub fn draw(frame: &mut Frame, app: &App) {
     sections::draw_title(frame, chunks[0]);
     sections::draw_timer(frame, chunks[1], app);
     sections::draw_todos(frame, chunks[2], app);
-    sections::draw_action_bar(frame, chunks[3], app);
+    sections::draw_action_bar(frame, chunks[3], app, flashing);
+    if app.show_heatmap {
+        heatmap::draw_heatmap(frame, chunks[4], app);
+    }
     if app.show_history {
-        sections::draw_history(frame, chunks[4], app);
+        sections::draw_history(frame, chunks[5], app);
     }
 
     if let Some(ref modal) = app.modal {
-        modal::draw_modal(frame, area, modal);
+        modal::draw_modal(frame, area, app, modal);
     }
 }
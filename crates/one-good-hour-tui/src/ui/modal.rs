@@ -6,33 +6,52 @@ use ratatui::{
     Frame,
 };
 
-use one_good_hour_core::app::ModalKind;
+use one_good_hour_core::app::{App, ModalKind};
+use one_good_hour_core::types::format_time;
 
 use super::colors;
 
-pub(super) fn draw_modal(frame: &mut Frame, area: Rect, modal: &ModalKind) {
-    if matches!(modal, ModalKind::Help) {
-        draw_help_modal(frame, area);
-        return;
+pub(super) fn draw_modal(frame: &mut Frame, area: Rect, app: &App, modal: &ModalKind) {
+    match modal {
+        ModalKind::Help => return draw_help_modal(frame, area),
+        ModalKind::Stats => return super::stats::draw_stats_modal(frame, area, app),
+        _ => {}
     }
 
     let (title, body) = match modal {
-        ModalKind::CompleteSession => (
-            "Complete Session",
-            "Complete this session and save to history?\n\n[y] Yes  [n] No",
-        ),
+        ModalKind::CompleteSession => ("Complete Session", complete_session_body(app)),
         ModalKind::ClearNotes => (
             "Clear History",
-            "Clear all completed sessions?\n\n[y] Yes  [n] No",
+            "Clear all completed sessions?\n\n[y] Yes  [n] No".to_string(),
         ),
         ModalKind::NewSession => (
             "New Session",
-            "Start fresh? This clears all tasks and history.\n\n[y] Yes  [n] No",
+            "Start fresh? This clears all tasks and history.\n\n[y] Yes  [n] No".to_string(),
+        ),
+        ModalKind::Away { seconds } => (
+            "Welcome Back",
+            format!(
+                "Away for {}. Count it as focus time?\n\n[y] Count  [n] Stay paused",
+                format_time(*seconds)
+            ),
         ),
-        ModalKind::Help => unreachable!(),
+        ModalKind::Resume { time_left, tasks } => (
+            "Resume Session",
+            format!(
+                "Found an unfinished session ({tasks} tasks, {} left). Resume it?\n\n[y] Resume  [n] Start fresh",
+                format_time(*time_left)
+            ),
+        ),
+        ModalKind::Export => ("Export History", export_body(app)),
+        ModalKind::Help | ModalKind::Stats => unreachable!(),
     };
 
-    let modal_area = centered_rect_fixed(40, 7, area);
+    // The export list has a second row of formats and one per row of templates
+    let extra = match modal {
+        ModalKind::Export => 1 + app.templates.len().min(9).div_ceil(TEMPLATES_PER_ROW) as u16,
+        _ => 0,
+    };
+    let modal_area = centered_rect_fixed(40, 8 + extra, area);
     frame.render_widget(Clear, modal_area);
 
     let block = Block::default()
@@ -49,10 +68,43 @@ pub(super) fn draw_modal(frame: &mut Frame, area: Rect, modal: &ModalKind) {
     frame.render_widget(para, modal_area);
 }
 
-fn draw_help_modal(frame: &mut Frame, area: Rect) {
-    let modal_area = centered_rect(60, 70, area);
-    frame.render_widget(Clear, modal_area);
+fn complete_session_body(app: &App) -> String {
+    let unfinished = app.active_note.unfinished_todos().count();
+    let mut body = String::from("Complete this session and save to history?");
+    if unfinished > 0 && app.carry_over {
+        body.push_str(" Unfinished tasks carry over.");
+    }
+    body.push_str("\n\n[y] Yes  [n] No");
+    if unfinished > 0 {
+        body.push_str(&format!("\n[k] Keep {unfinished} unfinished  [f] Start fresh"));
+    }
+    body
+}
 
+const TEMPLATES_PER_ROW: usize = 3;
+
+fn export_body(app: &App) -> String {
+    let mut body = String::from(
+        "Save all sessions to a file in the current directory?\n\n\
+         [m] Markdown  [j] JSON\n[c] CSV  [i] iCalendar",
+    );
+    // Digit keys pick the first nine templates
+    let names: Vec<String> = app
+        .templates
+        .iter()
+        .take(9)
+        .enumerate()
+        .map(|(i, t)| format!("[{}] {}", i + 1, t.name()))
+        .collect();
+    for row in names.chunks(TEMPLATES_PER_ROW) {
+        body.push('\n');
+        body.push_str(&row.join("  "));
+    }
+    body.push_str("\n[n] Cancel");
+    body
+}
+
+fn draw_help_modal(frame: &mut Frame, area: Rect) {
     let block = Block::default()
         .title(" Shortcuts ")
         .borders(Borders::ALL)
@@ -65,10 +117,21 @@ fn draw_help_modal(frame: &mut Frame, area: Rect) {
         ("r", "Reset timer", "k/↑", "Move up"),
         ("Enter", "Edit task", "x", "Check off task"),
         ("d", "Clear task", "c", "Complete session"),
+        ("a", "Add task below", "i", "Add task above"),
+        ("X", "Delete task", "J/K", "Move task down/up"),
         ("h/←", "Prev history", "l/→", "Next history"),
-        ("y", "Copy markdown", "D", "Clear history"),
+        ("y", "Copy history", "Y", "Copy this session"),
         ("N", "New session", "q", "Quit"),
         ("H", "Toggle history", "?", "Show help"),
+        ("s", "Session length", "b", "Skip break"),
+        ("A", "Auto-start phases", "o", "Overtime mode"),
+        ("+", "Add 5 minutes", "-", "Remove 5 minutes"),
+        ("g", "After sleep", "w", "Work on task"),
+        ("C", "Carry over tasks", "P", "Save history"),
+        ("u", "Undo", "^R", "Redo"),
+        ("e", "Export to file", "T", "Copy template"),
+        ("D", "Clear history", "S", "Stats"),
+        ("M", "Focus heatmap", "", ""),
     ];
 
     let mut lines: Vec<Line> = vec![Line::from("")];
@@ -86,12 +149,17 @@ fn draw_help_modal(frame: &mut Frame, area: Rect) {
         Style::default().fg(colors::dim()),
     )));
 
+    // Fit the shortcut list, but never grow past the terminal
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let modal_area = centered_rect_fixed(60, height, area);
+    frame.render_widget(Clear, modal_area);
+
     let para = Paragraph::new(lines).block(block);
 
     frame.render_widget(para, modal_area);
 }
 
-fn centered_rect_fixed(percent_x: u16, height: u16, area: Rect) -> Rect {
+pub(super) fn centered_rect_fixed(percent_x: u16, height: u16, area: Rect) -> Rect {
     let vert = Layout::vertical([
         Constraint::Min(0),
         Constraint::Length(height),
@@ -106,19 +174,3 @@ fn centered_rect_fixed(percent_x: u16, height: u16, area: Rect) -> Rect {
     ])
     .split(vert[1])[1]
 }
-
-fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
-    let vert = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(area);
-
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(vert[1])[1]
-}
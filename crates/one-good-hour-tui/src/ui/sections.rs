@@ -7,7 +7,7 @@ use ratatui::{
 };
 
 use one_good_hour_core::app::{App, InputMode};
-use one_good_hour_core::types::{format_time, Todo, MAX_TIME};
+use one_good_hour_core::types::{format_overtime, format_time, Todo};
 
 use super::gradient_bar::GradientBar;
 
@@ -30,27 +30,36 @@ pub(super) fn draw_timer(frame: &mut Frame, area: Rect, app: &App) {
     .split(area);
 
     // Progress bar with embedded timer
-    let progress = 1.0 - (app.active_note.time_left as f64 / MAX_TIME as f64);
-    let timer_text = format_time(app.active_note.time_left);
+    let phase = app.phase();
+    let progress = app.phase_progress();
+    let timer_text = app.timer_display();
     let bar = GradientBar {
-        ratio: progress.clamp(0.0, 1.0),
-        label: format!("{timer_text} · {}%", (progress * 100.0) as u16),
+        ratio: progress,
+        label: format!("{} · {timer_text} · {}%", phase.label(), (progress * 100.0) as u16),
     };
     frame.render_widget(bar, chunks[0]);
 
     // Controls hint
-    let status = if app.active_note.is_running {
+    let status = if app.is_timer_running() && app.active_note.overtime > 0 && !phase.is_break() {
+        "▶ Overtime"
+    } else if app.is_timer_running() {
         "▶ Running"
-    } else if app.active_note.time_left == 0 {
+    } else if app.phase_time_left() == 0 && !app.allow_overtime {
         "✓ Done"
     } else {
         "⏸ Paused"
     };
-    let hint = Paragraph::new(format!(
-        "{status}  [Space] Play/Pause  [r] Reset"
-    ))
-    .alignment(Alignment::Center)
-    .style(Style::default().fg(colors::dim()));
+    let hint = if phase.is_break() {
+        format!("{status}  [Space] Play/Pause  [r] Reset  [b] Skip break")
+    } else {
+        format!(
+            "{status}  [Space] Play/Pause  [r] Reset  [s] {} min",
+            app.active_note.duration / 60
+        )
+    };
+    let hint = Paragraph::new(hint)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(colors::dim()));
     frame.render_widget(hint, chunks[1]);
 }
 
@@ -63,13 +72,8 @@ pub(super) fn draw_todos(frame: &mut Frame, area: Rect, app: &App) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let rows = Layout::vertical([
-        Constraint::Length(1),
-        Constraint::Length(1),
-        Constraint::Length(1),
-        Constraint::Length(1),
-    ])
-    .split(inner);
+    let rows = Layout::vertical(vec![Constraint::Length(1); app.active_note.todos.len()])
+        .split(inner);
 
     for (i, todo) in app.active_note.todos.iter().enumerate() {
         let is_selected = i == app.selected_todo;
@@ -78,13 +82,11 @@ pub(super) fn draw_todos(frame: &mut Frame, area: Rect, app: &App) {
         let checkbox = if todo.completed { "[x]" } else { "[ ]" };
         let text = if todo.text.is_empty() && !is_editing {
             "(empty)".to_string()
-        } else if is_editing {
-            format!("{}▎", todo.text)
         } else {
             todo.text.clone()
         };
 
-        let line = Line::from(vec![
+        let prefix = vec![
             Span::styled(
                 if is_selected { "▸ " } else { "  " },
                 Style::default().fg(colors::bright()),
@@ -101,8 +103,37 @@ pub(super) fn draw_todos(frame: &mut Frame, area: Rect, app: &App) {
                 format!("{}. ", i + 1),
                 Style::default().fg(colors::faint()),
             ),
-            Span::styled(text, todo_text_style(todo, is_editing)),
-        ]);
+        ];
+
+        if is_editing {
+            // Put the terminal cursor where the next character will go
+            let column = Line::from(prefix.clone()).width()
+                + Span::raw(app.editor.before_cursor()).width();
+            let x = (rows[i].x + column as u16).min(rows[i].right().saturating_sub(1));
+            frame.set_cursor_position((x, rows[i].y));
+        }
+
+        let mut spans = prefix;
+        spans.push(Span::styled(text, todo_text_style(todo, is_editing)));
+        if let Some(from) = todo.carried_from {
+            spans.push(Span::styled(
+                format!(" ↻{from}"),
+                Style::default().fg(colors::faint()),
+            ));
+        }
+        let is_active = app.active_note.active_todo == Some(i);
+        if is_active || todo.time_spent > 0 {
+            let (marker, fg) = if is_active {
+                ("◷", colors::bright())
+            } else {
+                ("·", colors::faint())
+            };
+            spans.push(Span::styled(
+                format!(" {marker} {}", format_time(todo.time_spent)),
+                Style::default().fg(fg),
+            ));
+        }
+        let line = Line::from(spans);
 
         let style = if is_selected {
             Style::default().bg(colors::selection_bg())
@@ -133,9 +164,17 @@ fn todo_text_style(todo: &Todo, is_editing: bool) -> Style {
     Style::default().fg(fg).add_modifier(modifier)
 }
 
-pub(super) fn draw_action_bar(frame: &mut Frame, area: Rect, app: &App) {
+pub(super) fn draw_action_bar(frame: &mut Frame, area: Rect, app: &App, flashing: bool) {
     let status = if let Some(ref msg) = app.status_message {
-        Line::from(Span::styled(msg.as_str(), Style::default().fg(colors::bright())))
+        let style = if flashing {
+            Style::default()
+                .fg(colors::label_text())
+                .bg(colors::bright())
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(colors::bright())
+        };
+        Line::from(Span::styled(format!(" {msg} "), style))
     } else {
         Line::from(vec![
             Span::styled("[x]", Style::default().fg(colors::bright())),
@@ -188,7 +227,7 @@ pub(super) fn draw_history(frame: &mut Frame, area: Rect, app: &App) {
     .split(inner);
 
     // Header
-    let header = Paragraph::new(Line::from(vec![
+    let mut header = vec![
         Span::styled("[←/h] ", Style::default().fg(colors::dim())),
         Span::styled(
             format!("Session {} of {}", idx + 1, total),
@@ -196,9 +235,15 @@ pub(super) fn draw_history(frame: &mut Frame, area: Rect, app: &App) {
                 .fg(colors::bright())
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" [→/l]", Style::default().fg(colors::dim())),
-    ]))
-    .alignment(Alignment::Center);
+    ];
+    if let Some(span) = note.span() {
+        header.push(Span::styled(
+            format!(" · {span}"),
+            Style::default().fg(colors::dim()),
+        ));
+    }
+    header.push(Span::styled(" [→/l]", Style::default().fg(colors::dim())));
+    let header = Paragraph::new(Line::from(header)).alignment(Alignment::Center);
     frame.render_widget(header, chunks[0]);
 
     // Todos in the completed note
@@ -225,6 +270,14 @@ pub(super) fn draw_history(frame: &mut Frame, area: Rect, app: &App) {
                             Modifier::empty()
                         }),
                 ),
+                Span::styled(
+                    if todo.time_spent > 0 {
+                        format!("  {}", format_time(todo.time_spent))
+                    } else {
+                        String::new()
+                    },
+                    Style::default().fg(colors::dim()),
+                ),
             ]));
         }
     }
@@ -232,14 +285,28 @@ pub(super) fn draw_history(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(todos_para, chunks[1]);
 
     // Footer
+    let mut time = format!(
+        "Time: {} / {}",
+        format_time(note.time_spent),
+        format_time(note.duration)
+    );
+    if note.overtime > 0 {
+        time.push_str(&format!(" {}", format_overtime(note.overtime)));
+    }
+    if note.interruptions > 0 {
+        time.push_str(&format!(
+            "  Paused: {}× {}",
+            note.interruptions,
+            format_time(note.paused_time)
+        ));
+    }
     let footer = Paragraph::new(Line::from(vec![
-        Span::styled(
-            format!("Time: {}", format_time(note.time_spent)),
-            Style::default().fg(colors::dim()),
-        ),
+        Span::styled(time, Style::default().fg(colors::dim())),
         Span::raw("  "),
         Span::styled("[y]", Style::default().fg(colors::bright())),
-        Span::styled(" Copy  ", Style::default().fg(colors::normal())),
+        Span::styled(" Copy all  ", Style::default().fg(colors::normal())),
+        Span::styled("[Y]", Style::default().fg(colors::bright())),
+        Span::styled(" Copy this  ", Style::default().fg(colors::normal())),
         Span::styled("[D]", Style::default().fg(colors::bright())),
         Span::styled(" Clear", Style::default().fg(colors::normal())),
     ]))
@@ -0,0 +1,101 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use one_good_hour_core::app::App;
+use one_good_hour_core::stats::{format_minutes, GOOD_HOUR};
+
+use super::colors;
+use super::modal::centered_rect_fixed;
+
+/// Width of the daily bars, in cells.
+const BAR_WIDTH: u64 = 20;
+
+pub(super) fn draw_stats_modal(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .title(" Stats ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors::bright()));
+
+    let stats = app.stats();
+    let today = app.today();
+    let label = Style::default().fg(colors::dim());
+    let value = Style::default().fg(colors::bright());
+    let row = |name: &str, main: String, extra: String| {
+        Line::from(vec![
+            Span::styled(format!("  {name:<12}"), label),
+            Span::styled(format!("{main:>9}"), value),
+            Span::styled(format!("  {extra}"), Style::default().fg(colors::normal())),
+        ])
+    };
+
+    let today_total = stats.days.iter().find(|d| d.date == today);
+    let plural = |n: usize, word: &str| format!("{n} {word}{}", if n == 1 { "" } else { "s" });
+    let mut lines = vec![
+        Line::from(""),
+        row(
+            "Today",
+            format_minutes(stats.day(today)),
+            today_total.map_or(String::new(), |d| plural(d.sessions as usize, "session")),
+        ),
+        row("This week", format_minutes(stats.week(today)), String::new()),
+        row(
+            "All time",
+            format_minutes(stats.focused),
+            format!(
+                "{}, avg {}",
+                plural(stats.sessions, "session"),
+                format_minutes(stats.average_session())
+            ),
+        ),
+        row(
+            "Tasks done",
+            format!("{} of {}", stats.todos_completed, stats.todos_planned),
+            stats
+                .completion_rate()
+                .map_or(String::new(), |r| format!("{:.0}%", r * 100.0)),
+        ),
+        row(
+            "Streak",
+            plural(stats.current_streak as usize, "day"),
+            format!("best {}", stats.longest_streak),
+        ),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Last 7 days",
+            Style::default().fg(colors::normal()).add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    // One bar per day, scaled so the longest day (or a good hour) fills it
+    let week = stats.recent_days(today, 7);
+    let scale = week.iter().map(|d| d.seconds).max().unwrap_or(0).max(GOOD_HOUR);
+    for day in week {
+        let seconds = day.seconds;
+        let filled = (seconds * BAR_WIDTH).div_ceil(scale) as usize;
+        let color = if seconds >= GOOD_HOUR { colors::bright() } else { colors::normal() };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} ", day.date.format("%a %d")), label),
+            Span::styled("█".repeat(filled), Style::default().fg(color)),
+            Span::styled(
+                "░".repeat(BAR_WIDTH as usize - filled),
+                Style::default().fg(colors::faint()),
+            ),
+            Span::styled(format!(" {}", format_minutes(seconds)), label),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "            [Esc] Close",
+        Style::default().fg(colors::dim()),
+    )));
+
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let modal_area = centered_rect_fixed(60, height, area);
+    frame.render_widget(Clear, modal_area);
+    frame.render_widget(Paragraph::new(lines).block(block), modal_area);
+}
@@ -94,6 +94,8 @@ const THEMES = [
 ];
 
 let themeModalOpen = false;
+// The number being typed into the prompt overlay, and what it's for
+let numberPrompt = null;
 let themeSelectedIndex = 0;
 let currentThemeId = localStorage.getItem("1gh-theme") || "ember";
 
@@ -124,10 +126,7 @@ document.addEventListener("paste", (e) => {
     if (!currentState) return;
     if (!currentState.input_mode || !currentState.input_mode.startsWith("editing:")) return;
     const text = (e.clipboardData || window.clipboardData).getData("text");
-    for (const ch of text) {
-        if (ch === "\n" || ch === "\r") continue;
-        sendAction("edit_char", ch);
-    }
+    if (text) sendAction("edit_char", text);
     e.preventDefault();
 });
 
@@ -137,6 +136,11 @@ function handleKey(e) {
         return;
     }
 
+    if (numberPrompt) {
+        handlePromptKey(e);
+        return;
+    }
+
     if (!currentState) return;
 
     const mode = currentState.input_mode;
@@ -154,6 +158,13 @@ function handleNormalKey(e) {
     const key = e.key;
     let handled = true;
 
+    // Ctrl-R would reload the webview; use it for redo instead
+    if ((e.ctrlKey || e.metaKey) && (key === "r" || key === "z" || key === "Z")) {
+        sendAction(key === "z" && !e.shiftKey ? "undo" : "redo");
+        e.preventDefault();
+        return;
+    }
+
     switch (key) {
         case " ":
             sendAction("toggle_timer");
@@ -161,6 +172,38 @@ function handleNormalKey(e) {
         case "r":
             sendAction("reset_timer");
             break;
+        case "u":
+            sendAction("undo");
+            break;
+        case "s":
+            sendAction("cycle_session_length");
+            break;
+        case "L":
+            openPrompt("Session length in minutes (now " + Math.floor(currentState.duration / 60) + ")",
+                1440, "set_session_length");
+            break;
+        case "m":
+            openPrompt("Max tasks (now " + currentState.todo_cap + ")", 8, "set_todo_cap");
+            break;
+        case "+":
+        case "=":
+            sendAction("extend_session");
+            break;
+        case "-":
+            sendAction("shorten_session");
+            break;
+        case "b":
+            sendAction("skip_break");
+            break;
+        case "A":
+            sendAction("toggle_auto_start");
+            break;
+        case "o":
+            sendAction("toggle_overtime");
+            break;
+        case "g":
+            sendAction("cycle_gap_policy");
+            break;
         case "j":
         case "ArrowDown":
             sendAction("move_down");
@@ -178,6 +221,31 @@ function handleNormalKey(e) {
         case "d":
             sendAction("remove_todo");
             break;
+        case "a":
+            sendAction("add_todo");
+            break;
+        case "i":
+            sendAction("insert_todo");
+            break;
+        case "X":
+        case "Delete":
+            sendAction("delete_todo");
+            break;
+        case "J":
+            sendAction("move_todo_down");
+            break;
+        case "K":
+            sendAction("move_todo_up");
+            break;
+        case "w":
+            sendAction("toggle_active_todo");
+            break;
+        case "C":
+            sendAction("toggle_carry_over");
+            break;
+        case "P":
+            sendAction("start_saving_history");
+            break;
         case "c":
             sendAction("complete_session");
             break;
@@ -192,6 +260,18 @@ function handleNormalKey(e) {
         case "y":
             sendAction("copy_markdown");
             break;
+        case "Y":
+            sendAction("copy_note");
+            break;
+        case "S":
+            sendAction("show_stats");
+            break;
+        case "e":
+            sendAction("show_export");
+            break;
+        case "T":
+            sendAction("cycle_copy_template");
+            break;
         case "D":
             sendAction("clear_notes");
             break;
@@ -216,10 +296,29 @@ function handleNormalKey(e) {
 
 function handleEditingKey(e) {
     if (e.metaKey || e.ctrlKey) {
-        if (e.key === "Backspace") {
-            sendAction("edit_clear");
-            e.preventDefault();
+        let handled = true;
+        switch (e.key) {
+            case "Backspace":
+                sendAction(e.metaKey ? "edit_delete_to_start" : "edit_delete_word");
+                break;
+            case "w":
+                sendAction("edit_delete_word");
+                break;
+            case "u":
+                sendAction("edit_delete_to_start");
+                break;
+            case "a":
+            case "ArrowLeft":
+                sendAction("cursor_home");
+                break;
+            case "e":
+            case "ArrowRight":
+                sendAction("cursor_end");
+                break;
+            default:
+                handled = false;
         }
+        if (handled) e.preventDefault();
         return;
     }
 
@@ -234,6 +333,21 @@ function handleEditingKey(e) {
         case "Backspace":
             sendAction("edit_backspace");
             break;
+        case "Delete":
+            sendAction("edit_delete");
+            break;
+        case "ArrowLeft":
+            sendAction("cursor_left");
+            break;
+        case "ArrowRight":
+            sendAction("cursor_right");
+            break;
+        case "Home":
+            sendAction("cursor_home");
+            break;
+        case "End":
+            sendAction("cursor_end");
+            break;
         default:
             if (key.length === 1) {
                 sendAction("edit_char", key);
@@ -258,8 +372,30 @@ function handleModalKey(e) {
         case "Escape":
             sendAction("dismiss_modal");
             break;
+        case "k":
+            sendAction("complete_keep_unfinished");
+            break;
+        case "f":
+            sendAction("complete_fresh");
+            break;
+        case "m":
+            sendAction("export", "md");
+            break;
+        case "j":
+            sendAction("export", "json");
+            break;
+        case "c":
+            sendAction("export", "csv");
+            break;
+        case "i":
+            sendAction("export", "ics");
+            break;
         default:
-            handled = false;
+            if (key >= "1" && key <= "9") {
+                sendAction("export_template", String(Number(key) - 1));
+            } else {
+                handled = false;
+            }
     }
 
     if (handled) e.preventDefault();
@@ -276,6 +412,11 @@ function render(state) {
     renderHistory(state);
     renderModal(state);
 
+    if (state.milestone) {
+        playChime();
+        flashActionBar();
+    }
+
     if (state.sound_pending) {
         playBeep();
         sendAction("clear_sound");
@@ -289,21 +430,48 @@ function renderTimer(state) {
 
     const pct = Math.round(state.progress * 100);
     fill.style.width = (state.progress * 100) + "%";
-    label.textContent = state.timer_display + " \u00b7 " + pct + "%";
+    label.textContent = state.phase_label + " \u00b7 " + state.timer_display + " \u00b7 " + pct + "%";
 
     let statusIcon;
-    if (state.is_running) {
+    if (state.is_running && state.overtime > 0) {
+        statusIcon = "\u25b6 Overtime";
+    } else if (state.is_running) {
         statusIcon = "\u25b6 Running";
-    } else if (state.time_left === 0) {
+    } else if (state.time_left === 0 && !state.allow_overtime) {
         statusIcon = "\u2713 Done";
     } else {
         statusIcon = "\u23f8 Paused";
     }
-    status.textContent = statusIcon + "  [Space] Play/Pause  [r] Reset";
+    if (state.phase === "focus") {
+        status.textContent = statusIcon + "  [Space] Play/Pause  [r] Reset  [s] " +
+            Math.floor(state.duration / 60) + " min";
+    } else {
+        status.textContent = statusIcon + "  [Space] Play/Pause  [r] Reset  [b] Skip break";
+    }
+}
+
+// Keep one row element per task, adding or removing rows as the list changes
+function syncTodoRows(count) {
+    const list = document.getElementById("todo-list");
+    while (list.children.length < count) {
+        const row = document.createElement("div");
+        row.id = "todo-" + list.children.length;
+        row.className = "todo-row";
+        row.innerHTML =
+            '<span class="todo-selector">  </span> ' +
+            '<span class="todo-checkbox">[ ]</span> ' +
+            '<span class="todo-text">(empty)</span>' +
+            '<span class="todo-time"></span>';
+        list.appendChild(row);
+    }
+    while (list.children.length > count) {
+        list.removeChild(list.lastChild);
+    }
 }
 
 function renderTodos(state) {
-    for (let i = 0; i < 4; i++) {
+    syncTodoRows(state.todos.length);
+    for (let i = 0; i < state.todos.length; i++) {
         const row = document.getElementById("todo-" + i);
         const todo = state.todos[i];
         const isSelected = i === state.selected_todo;
@@ -324,7 +492,11 @@ function renderTodos(state) {
         // Text
         const textEl = row.querySelector(".todo-text");
         if (isEditing) {
-            textEl.innerHTML = escapeHtml(todo.text) + '<span class="cursor-char">\u258e</span>';
+            const chars = Array.from(todo.text);
+            const before = chars.slice(0, state.edit_cursor).join("");
+            const after = chars.slice(state.edit_cursor).join("");
+            textEl.innerHTML =
+                escapeHtml(before) + '<span class="cursor-char">\u258e</span>' + escapeHtml(after);
             textEl.className = "todo-text editing";
         } else if (todo.text === "") {
             textEl.textContent = "(empty)";
@@ -336,13 +508,30 @@ function renderTodos(state) {
             textEl.textContent = todo.text;
             textEl.className = "todo-text";
         }
+
+        // Carry-over link and time tracked on the task
+        const timeEl = row.querySelector(".todo-time");
+        let extra = todo.carried_from ? " \u21bb" + todo.carried_from : "";
+        if (todo.active || todo.time_spent) {
+            extra += (todo.active ? " \u25f7 " : " \u00b7 ") + (todo.time_spent || "0:00");
+        }
+        timeEl.textContent = extra;
+        timeEl.className = "todo-time" + (todo.active ? " active" : "");
     }
 }
 
+// The backend hands each status message out once; keep it on screen briefly
+let statusMessage = null;
+let statusUntil = 0;
+
 function renderActionBar(state) {
     const bar = document.getElementById("action-bar-text");
     if (state.status_message) {
-        bar.innerHTML = '<span class="key-hint">' + escapeHtml(state.status_message) + '</span>';
+        statusMessage = state.status_message;
+        statusUntil = Date.now() + 2000;
+    }
+    if (statusMessage && Date.now() < statusUntil) {
+        bar.innerHTML = '<span class="key-hint">' + escapeHtml(statusMessage) + '</span>';
     } else {
         bar.innerHTML =
             '<span class="key-hint">[x]</span> Check  ' +
@@ -385,6 +574,7 @@ function renderHistory(state) {
     header.innerHTML =
         '<span style="color:var(--dim)">[←/h] </span>' +
         '<span style="color:var(--bright);font-weight:bold">Session ' + (idx + 1) + ' of ' + total + '</span>' +
+        (note.span ? '<span style="color:var(--dim)"> · ' + escapeHtml(note.span) + '</span>' : '') +
         '<span style="color:var(--dim)"> [→/l]</span>';
 
     todosEl.innerHTML = "";
@@ -397,23 +587,36 @@ function renderHistory(state) {
         const textClass = todo.completed ? "todo-text completed" : "todo-text";
         div.innerHTML =
             '<span class="' + checkClass + '">  ' + check + ' </span>' +
-            '<span class="' + textClass + '">' + escapeHtml(todo.text) + '</span>';
+            '<span class="' + textClass + '">' + escapeHtml(todo.text) + '</span>' +
+            (todo.time_spent ? '<span class="todo-time">  ' + todo.time_spent + '</span>' : '');
         todosEl.appendChild(div);
     }
 
-    const timeDisplay = note.time_spent;
+    let timeDisplay = note.time_spent + " / " + note.duration;
+    if (note.overtime) timeDisplay += " " + note.overtime;
+    if (note.interruptions > 0) {
+        timeDisplay += "  Paused: " + note.interruptions + "\u00d7 " + note.paused_time;
+    }
     footer.innerHTML =
         '<span style="color:var(--dim)">Time: ' + timeDisplay + '</span>  ' +
         '<span class="key-hint">[y]</span> ' +
-        '<span style="color:var(--normal)">Copy  </span>' +
+        '<span style="color:var(--normal)">Copy all' +
+        (state.copy_template !== "Markdown" ? ' as ' + escapeHtml(state.copy_template) : '') + '  </span>' +
+        '<span class="key-hint">[Y]</span> ' +
+        '<span style="color:var(--normal)">Copy this  </span>' +
         '<span class="key-hint">[D]</span> ' +
         '<span style="color:var(--normal)">Clear</span>';
 }
 
+// Modal shown by the previous render, to fetch stats only on opening
+let lastModal = null;
+
 function renderModal(state) {
     const overlay = document.getElementById("modal-overlay");
     const title = document.getElementById("modal-title");
     const body = document.getElementById("modal-body");
+    const previousModal = lastModal;
+    lastModal = state.modal;
 
     if (!state.modal) {
         overlay.classList.add("hidden");
@@ -425,12 +628,46 @@ function renderModal(state) {
     if (state.modal === "help") {
         title.textContent = "Shortcuts";
         body.innerHTML = renderHelpContent();
+    } else if (state.modal === "stats") {
+        title.textContent = "Stats";
+        // Fetch once per opening; ticks re-render the modal every second
+        if (previousModal !== "stats") {
+            body.innerHTML = "";
+            invoke("get_stats")
+                .then(stats => { body.innerHTML = renderStatsContent(stats); })
+                .catch(e => console.error("stats error:", e));
+        }
     } else if (state.modal === "complete_session") {
         title.textContent = "Complete Session";
-        body.innerHTML = 'Complete this session and save to history?\n\n<span class="key-hint">[y]</span> Yes  <span class="key-hint">[n]</span> No';
+        let text = 'Complete this session and save to history?';
+        if (state.unfinished > 0 && state.carry_over) text += ' Unfinished tasks carry over.';
+        text += '\n\n<span class="key-hint">[y]</span> Yes  <span class="key-hint">[n]</span> No';
+        if (state.unfinished > 0) {
+            text += '\n<span class="key-hint">[k]</span> Keep ' + state.unfinished + ' unfinished  ' +
+                '<span class="key-hint">[f]</span> Start fresh';
+        }
+        body.innerHTML = text;
     } else if (state.modal === "clear_notes") {
         title.textContent = "Clear History";
         body.innerHTML = 'Clear all completed sessions?\n\n<span class="key-hint">[y]</span> Yes  <span class="key-hint">[n]</span> No';
+    } else if (state.modal === "away") {
+        title.textContent = "Welcome Back";
+        body.innerHTML = 'Away for ' + escapeHtml(state.away_time) + '. Count it as focus time?\n\n<span class="key-hint">[y]</span> Count  <span class="key-hint">[n]</span> Stay paused';
+    } else if (state.modal === "resume") {
+        title.textContent = "Resume Session";
+        body.innerHTML = 'Found an unfinished session (' + state.resume.tasks + ' tasks, ' +
+            escapeHtml(state.resume.time_left) + ' left). Resume it?\n\n<span class="key-hint">[y]</span> Resume  <span class="key-hint">[n]</span> Start fresh';
+    } else if (state.modal === "export") {
+        title.textContent = "Export History";
+        body.innerHTML = 'Save all sessions to a file in your Downloads folder?\n\n' +
+            '<span class="key-hint">[m]</span> Markdown  <span class="key-hint">[j]</span> JSON  ' +
+            '<span class="key-hint">[c]</span> CSV  <span class="key-hint">[i]</span> iCalendar';
+        const templates = state.templates.slice(0, 9);
+        for (let i = 0; i < templates.length; i++) {
+            body.innerHTML += (i % 3 === 0 ? '\n' : '  ') +
+                '<span class="key-hint">[' + (i + 1) + ']</span> ' + escapeHtml(templates[i]);
+        }
+        body.innerHTML += '\n<span class="key-hint">[n]</span> Cancel';
     } else if (state.modal === "new_session") {
         title.textContent = "New Session";
         body.innerHTML = 'Start fresh? This clears all tasks and history.\n\n<span class="key-hint">[y]</span> Yes  <span class="key-hint">[n]</span> No';
@@ -443,10 +680,21 @@ function renderHelpContent() {
         ["r", "Reset timer", "k/\u2191", "Move up"],
         ["Enter", "Edit task", "x", "Check off task"],
         ["d", "Clear task", "c", "Complete session"],
+        ["a", "Add task below", "i", "Add task above"],
+        ["X", "Delete task", "J/K", "Move task down/up"],
         ["h/\u2190", "Prev history", "l/\u2192", "Next history"],
-        ["y", "Copy markdown", "D", "Clear history"],
+        ["y", "Copy history", "Y", "Copy this session"],
         ["N", "New session", "t", "Themes"],
         ["H", "Toggle history", "?", "Show help"],
+        ["s", "Session length", "b", "Skip break"],
+        ["A", "Auto-start phases", "o", "Overtime mode"],
+        ["+", "Add 5 minutes", "-", "Remove 5 minutes"],
+        ["g", "After sleep", "w", "Work on task"],
+        ["C", "Carry over tasks", "P", "Save history"],
+        ["u", "Undo", "^R", "Redo"],
+        ["e", "Export to file", "T", "Copy template"],
+        ["D", "Clear history", "S", "Stats"],
+        ["L", "Set length", "m", "Max tasks"],
     ];
 
     let html = '<div class="help-table">';
@@ -463,6 +711,46 @@ function renderHelpContent() {
     return html;
 }
 
+function renderStatsContent(stats) {
+    const plural = (n, word) => n + " " + word + (n === 1 ? "" : "s");
+    const rows = [
+        ["Today", stats.today, ""],
+        ["This week", stats.this_week, ""],
+        ["All time", stats.total, plural(stats.sessions, "session") + ", avg " + stats.average_session],
+        ["Tasks done", stats.todos_completed + " of " + stats.todos_planned,
+            stats.completion_rate === null ? "" : stats.completion_rate + "%"],
+        ["Streak", plural(stats.current_streak, "day"), "best " + stats.longest_streak],
+    ];
+    let html = '<div class="help-table">';
+    for (const [name, value, extra] of rows) {
+        html += '<div class="help-row">' +
+            '<span class="help-desc stats-label">' + escapeHtml(name) + '</span>' +
+            '<span class="help-key stats-value">' + escapeHtml(value) + '</span>' +
+            '<span class="help-desc">' + escapeHtml(extra) + '</span>' +
+            '</div>';
+    }
+    html += '</div>';
+
+    const chart = (title, periods) => {
+        // Scale so the longest period, or a good hour, fills the bar
+        const max = Math.max(60, ...periods.map(p => p.minutes));
+        let out = '<div class="stats-title">' + title + '</div><div class="help-table">';
+        for (const p of periods) {
+            out += '<div class="help-row">' +
+                '<span class="help-desc stats-label">' + escapeHtml(p.label) + '</span>' +
+                '<span class="stats-bar"><span class="stats-fill' + (p.good ? ' good' : '') +
+                '" style="width:' + Math.round(100 * p.minutes / max) + '%"></span></span>' +
+                '<span class="help-desc">' + escapeHtml(p.time) + '</span>' +
+                '</div>';
+        }
+        return out + '</div>';
+    };
+    html += chart("Last 7 days", stats.recent_days);
+    if (stats.weeks.length > 0) html += chart("Weeks", stats.weeks);
+    html += '<div class="help-close">[Esc] Close</div>';
+    return html;
+}
+
 // --- Theme modal ---
 function openThemeModal() {
     themeModalOpen = true;
@@ -547,6 +835,51 @@ function renderThemeModal() {
     }
 }
 
+// --- Number prompt ---
+// Typed here and checked by the backend, which reports a bad value
+function openPrompt(title, max, action) {
+    numberPrompt = { title, max, action, value: "" };
+    renderPrompt();
+    document.getElementById("prompt-overlay").classList.remove("hidden");
+}
+
+function closePrompt() {
+    numberPrompt = null;
+    document.getElementById("prompt-overlay").classList.add("hidden");
+}
+
+function handlePromptKey(e) {
+    const key = e.key;
+    let handled = true;
+
+    if (key >= "0" && key <= "9") {
+        if (numberPrompt.value.length < String(numberPrompt.max).length) {
+            numberPrompt.value += key;
+            renderPrompt();
+        }
+    } else if (key === "Backspace") {
+        numberPrompt.value = numberPrompt.value.slice(0, -1);
+        renderPrompt();
+    } else if (key === "Enter") {
+        if (numberPrompt.value) sendAction(numberPrompt.action, numberPrompt.value);
+        closePrompt();
+    } else if (key === "Escape") {
+        closePrompt();
+    } else {
+        handled = false;
+    }
+
+    if (handled) e.preventDefault();
+}
+
+function renderPrompt() {
+    document.getElementById("prompt-title").textContent = numberPrompt.title;
+    document.getElementById("prompt-input").innerHTML =
+        escapeHtml(numberPrompt.value) + '<span class="cursor-char">\u258e</span>';
+    document.getElementById("prompt-footer").textContent =
+        "1\u2013" + numberPrompt.max + "  [Enter] Set  [Esc] Cancel";
+}
+
 // --- Sound ---
 function playBeep() {
     try {
@@ -566,6 +899,37 @@ function playBeep() {
     }
 }
 
+// Two rising notes, distinct from the completion beep
+function playChime() {
+    try {
+        const ctx = new AudioContext();
+        [660, 990].forEach((freq, i) => {
+            const osc = ctx.createOscillator();
+            const gain = ctx.createGain();
+            const start = ctx.currentTime + i * 0.18;
+            osc.type = "triangle";
+            osc.frequency.value = freq;
+            gain.gain.setValueAtTime(0.25, start);
+            gain.gain.exponentialRampToValueAtTime(0.001, start + 0.16);
+            osc.connect(gain);
+            gain.connect(ctx.destination);
+            osc.start(start);
+            osc.stop(start + 0.16);
+        });
+    } catch (e) {
+        console.warn("Audio not available:", e);
+    }
+}
+
+let flashTimeout = null;
+
+function flashActionBar() {
+    const bar = document.getElementById("action-bar");
+    bar.classList.add("flash");
+    clearTimeout(flashTimeout);
+    flashTimeout = setTimeout(() => bar.classList.remove("flash"), 2000);
+}
+
 // --- Utilities ---
 function escapeHtml(str) {
     const div = document.createElement("div");
@@ -1,7 +1,14 @@
 use std::sync::Mutex;
 
 use one_good_hour_core::app::{App, InputMode, ModalKind};
-use one_good_hour_core::types::{format_time, MAX_TIME};
+use one_good_hour_core::cycle::Phase;
+use one_good_hour_core::export::Format;
+use one_good_hour_core::stats::{format_minutes, GOOD_HOUR};
+use one_good_hour_core::template;
+use one_good_hour_core::store::{HistoryStore, RecoveryFile};
+use one_good_hour_core::types::{
+    at_offset, format_overtime, format_time, Todo, DEFAULT_DURATION, MAX_TODO_CAP,
+};
 use serde::Serialize;
 use tauri::image::Image;
 use tauri::menu::{MenuBuilder, MenuItemBuilder};
@@ -16,47 +23,97 @@ pub struct TrayHolder(pub Mutex<Option<TrayIcon>>);
 pub struct TodoSnapshot {
     pub text: String,
     pub completed: bool,
+    /// Time credited to the task, if any.
+    pub time_spent: Option<String>,
+    /// Whether focused time is currently credited to this task.
+    pub active: bool,
+    /// Session the task was carried over from.
+    pub carried_from: Option<u64>,
+}
+
+fn todo_snapshot(todo: &Todo, active: bool) -> TodoSnapshot {
+    TodoSnapshot {
+        text: todo.text.clone(),
+        completed: todo.completed,
+        time_spent: (todo.time_spent > 0).then(|| format_time(todo.time_spent)),
+        active,
+        carried_from: todo.carried_from,
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct NoteSnapshot {
     pub todos: Vec<TodoSnapshot>,
     pub time_spent: String,
+    pub duration: String,
+    pub overtime: Option<String>,
+    pub interruptions: u32,
+    pub paused_time: String,
     pub completion_number: u64,
+    /// RFC 3339 in the offset the session ran in; `None` for old notes.
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub span: Option<String>,
+}
+
+/// The unfinished session offered by the `resume` modal.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumeSnapshot {
+    pub time_left: String,
+    pub tasks: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct AppSnapshot {
     pub time_left: u64,
+    pub duration: u64,
     pub is_running: bool,
+    pub phase: String,
+    pub phase_label: String,
+    pub auto_start: bool,
+    pub overtime: u64,
+    pub allow_overtime: bool,
     pub progress: f64,
     pub timer_display: String,
     pub todos: Vec<TodoSnapshot>,
     pub selected_todo: usize,
+    /// Most tasks a note may hold.
+    pub todo_cap: usize,
     pub input_mode: String,
     pub editing_index: Option<usize>,
+    /// Characters before the edit cursor.
+    pub edit_cursor: usize,
     pub modal: Option<String>,
+    pub away_time: Option<String>,
+    pub resume: Option<ResumeSnapshot>,
+    /// Unfinished tasks that could carry over when the session completes.
+    pub unfinished: usize,
+    pub carry_over: bool,
+    pub gap_policy: String,
     pub completed_notes: Vec<NoteSnapshot>,
     pub history_index: Option<usize>,
     pub history_total: usize,
     pub status_message: Option<String>,
     pub sound_pending: bool,
+    pub milestone: Option<String>,
     pub show_history: bool,
+    pub saving_history: bool,
+    /// Names of the user's templates, in export-key order.
+    pub templates: Vec<String>,
+    pub copy_template: String,
 }
 
 fn snapshot(app: &mut App) -> AppSnapshot {
-    let time_left = app.active_note.time_left;
-    let progress = (1.0 - time_left as f64 / MAX_TIME as f64).clamp(0.0, 1.0);
-    let timer_display = format_time(time_left);
+    let time_left = app.phase_time_left();
+    let progress = app.phase_progress();
+    let timer_display = app.timer_display();
 
     let todos: Vec<TodoSnapshot> = app
         .active_note
         .todos
         .iter()
-        .map(|t| TodoSnapshot {
-            text: t.text.clone(),
-            completed: t.completed,
-        })
+        .enumerate()
+        .map(|(i, t)| todo_snapshot(t, app.active_note.active_todo == Some(i)))
         .collect();
 
     let (input_mode, editing_index) = match &app.input_mode {
@@ -65,27 +122,46 @@ fn snapshot(app: &mut App) -> AppSnapshot {
         InputMode::Modal => ("modal".to_string(), None),
     };
 
+    let edit_cursor = app.editor.before_cursor().chars().count();
+
     let modal = app.modal.as_ref().map(|m| match m {
         ModalKind::CompleteSession => "complete_session".to_string(),
         ModalKind::ClearNotes => "clear_notes".to_string(),
         ModalKind::NewSession => "new_session".to_string(),
         ModalKind::Help => "help".to_string(),
+        ModalKind::Stats => "stats".to_string(),
+        ModalKind::Away { .. } => "away".to_string(),
+        ModalKind::Resume { .. } => "resume".to_string(),
+        ModalKind::Export => "export".to_string(),
     });
+    let away_time = match app.modal {
+        Some(ModalKind::Away { seconds }) => Some(format_time(seconds)),
+        _ => None,
+    };
+    let resume = match app.modal {
+        Some(ModalKind::Resume { time_left, tasks }) => Some(ResumeSnapshot {
+            time_left: format_time(time_left),
+            tasks,
+        }),
+        _ => None,
+    };
 
     let completed_notes: Vec<NoteSnapshot> = app
         .completed_notes
         .iter()
         .map(|n| NoteSnapshot {
-            todos: n
-                .todos
-                .iter()
-                .map(|t| TodoSnapshot {
-                    text: t.text.clone(),
-                    completed: t.completed,
-                })
-                .collect(),
+            todos: n.todos.iter().map(|t| todo_snapshot(t, false)).collect(),
             time_spent: format_time(n.time_spent),
+            duration: format_time(n.duration),
+            overtime: (n.overtime > 0).then(|| format_overtime(n.overtime)),
+            interruptions: n.interruptions,
+            paused_time: format_time(n.paused_time),
             completion_number: n.completion_number,
+            started_at: (n.finished_at > 0)
+                .then(|| at_offset(n.started_at, n.utc_offset).to_rfc3339()),
+            finished_at: (n.finished_at > 0)
+                .then(|| at_offset(n.finished_at, n.utc_offset).to_rfc3339()),
+            span: n.span(),
         })
         .collect();
 
@@ -94,24 +170,114 @@ fn snapshot(app: &mut App) -> AppSnapshot {
         app.sound_pending = false;
     }
 
+    let milestone = app.milestone_pending.take().map(|m| m.label());
     let status_message = app.status_message.take();
 
     AppSnapshot {
         time_left,
-        is_running: app.active_note.is_running,
+        duration: app.active_note.duration,
+        is_running: app.is_timer_running(),
+        phase: phase_id(app.phase()).to_string(),
+        phase_label: app.phase().label().to_string(),
+        auto_start: app.cycle.auto_start,
+        overtime: if app.on_break.is_none() { app.active_note.overtime } else { 0 },
+        allow_overtime: app.allow_overtime,
         progress,
         timer_display,
         todos,
         selected_todo: app.selected_todo,
+        todo_cap: app.todo_cap,
         input_mode,
         editing_index,
+        edit_cursor,
         modal,
+        away_time,
+        resume,
+        unfinished: app.active_note.unfinished_todos().count(),
+        carry_over: app.carry_over,
+        gap_policy: app.gap_policy.label().to_string(),
         completed_notes,
         history_index: app.history_index,
         history_total: app.completed_notes.len(),
         status_message,
         sound_pending,
+        milestone,
         show_history: app.show_history,
+        saving_history: app.is_saving_history(),
+        templates: app.templates.iter().map(|t| t.name().to_string()).collect(),
+        copy_template: app.copy_template_name().to_string(),
+    }
+}
+
+/// One day or week in the stats charts.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodSnapshot {
+    pub label: String,
+    pub minutes: u64,
+    pub time: String,
+    /// A day with at least a good hour of focus.
+    pub good: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsSnapshot {
+    pub today: String,
+    pub this_week: String,
+    pub total: String,
+    pub sessions: usize,
+    pub average_session: String,
+    pub todos_completed: usize,
+    pub todos_planned: usize,
+    /// Whole percent of planned tasks checked off.
+    pub completion_rate: Option<u32>,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// The last seven days, oldest first.
+    pub recent_days: Vec<PeriodSnapshot>,
+    /// Up to eight most recent weeks with focus, oldest first.
+    pub weeks: Vec<PeriodSnapshot>,
+}
+
+/// Most weeks listed in the stats view.
+const STATS_WEEKS: usize = 8;
+
+fn stats_snapshot(app: &App) -> StatsSnapshot {
+    let stats = app.stats();
+    let today = app.today();
+    let period = |label: String, seconds: u64| PeriodSnapshot {
+        label,
+        minutes: seconds / 60,
+        time: format_minutes(seconds),
+        good: seconds >= GOOD_HOUR,
+    };
+    StatsSnapshot {
+        today: format_minutes(stats.day(today)),
+        this_week: format_minutes(stats.week(today)),
+        total: format_minutes(stats.focused),
+        sessions: stats.sessions,
+        average_session: format_minutes(stats.average_session()),
+        todos_completed: stats.todos_completed,
+        todos_planned: stats.todos_planned,
+        completion_rate: stats.completion_rate().map(|r| (r * 100.0).round() as u32),
+        current_streak: stats.current_streak,
+        longest_streak: stats.longest_streak,
+        recent_days: stats
+            .recent_days(today, 7)
+            .iter()
+            .map(|d| period(d.date.format("%a %d").to_string(), d.seconds))
+            .collect(),
+        weeks: stats.weeks[stats.weeks.len().saturating_sub(STATS_WEEKS)..]
+            .iter()
+            .map(|w| period(w.start.format("%b %d").to_string(), w.seconds))
+            .collect(),
+    }
+}
+
+fn phase_id(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Focus => "focus",
+        Phase::ShortBreak => "short_break",
+        Phase::LongBreak => "long_break",
     }
 }
 
@@ -121,6 +287,12 @@ fn get_state(state: State<'_, AppState>) -> AppSnapshot {
     snapshot(&mut app)
 }
 
+#[tauri::command]
+fn get_stats(state: State<'_, AppState>) -> StatsSnapshot {
+    let app = state.0.lock().unwrap();
+    stats_snapshot(&app)
+}
+
 #[tauri::command]
 fn tick(state: State<'_, AppState>, app_handle: AppHandle) -> AppSnapshot {
     let mut app = state.0.lock().unwrap();
@@ -128,12 +300,30 @@ fn tick(state: State<'_, AppState>, app_handle: AppHandle) -> AppSnapshot {
     let snap = snapshot(&mut app);
     if let Some(tray) = app_handle.tray_by_id("main-tray") {
         let prefix = if snap.is_running { "\u{25cf}" } else { "\u{25cb}" };
-        let mins = snap.time_left.div_ceil(60);
-        let _ = tray.set_title(Some(format!("{} {}m", prefix, mins)));
+        let time = if snap.overtime > 0 {
+            format!("+{}m", snap.overtime / 60)
+        } else {
+            format!("{}m", snap.time_left.div_ceil(60))
+        };
+        let _ = tray.set_title(Some(format!("{} {} {}", prefix, snap.phase_label, time)));
     }
     snap
 }
 
+/// Longest session, in minutes, the frontend may set.
+const MAX_PAYLOAD_MINUTES: u64 = 24 * 60;
+
+/// A whole number of minutes from 1 to a day.
+fn payload_minutes(payload: Option<&str>) -> Result<u64, String> {
+    let minutes = payload
+        .and_then(|p| p.trim().parse::<u64>().ok())
+        .ok_or_else(|| format!("Invalid minutes: {}", payload.unwrap_or("")))?;
+    if !(1..=MAX_PAYLOAD_MINUTES).contains(&minutes) {
+        return Err(format!("Minutes must be between 1 and {MAX_PAYLOAD_MINUTES}"));
+    }
+    Ok(minutes)
+}
+
 #[tauri::command]
 fn action(
     name: String,
@@ -143,35 +333,88 @@ fn action(
     webview: WebviewWindow,
 ) -> AppSnapshot {
     let mut app = state.0.lock().unwrap();
+    let todo_count = app.active_note.todos.len();
     match name.as_str() {
         "toggle_timer" => app.toggle_timer(),
         "reset_timer" => app.reset_timer(),
+        "cycle_session_length" => app.cycle_session_length(),
+        "extend_session" => app.extend_session(),
+        "shorten_session" => app.shorten_session(),
+        "skip_break" => app.skip_break(),
+        "toggle_auto_start" => app.toggle_auto_start(),
+        "toggle_overtime" => app.toggle_overtime(),
+        "cycle_gap_policy" => app.cycle_gap_policy(),
+        "set_session_length" => match payload_minutes(payload.as_deref()) {
+            Ok(minutes) => app.set_session_length(minutes * 60),
+            Err(e) => app.status_message = Some(e),
+        },
         "move_up" => app.move_selection_up(),
         "move_down" => app.move_selection_down(),
         "start_editing" => app.start_editing(),
         "stop_editing" => app.stop_editing(),
         "toggle_todo" => app.toggle_todo(),
         "remove_todo" => app.remove_todo(),
+        "add_todo" => app.add_todo(),
+        "insert_todo" => app.insert_todo(),
+        "delete_todo" => app.delete_todo(),
+        "move_todo_up" => app.move_todo_up(),
+        "move_todo_down" => app.move_todo_down(),
+        "toggle_active_todo" => app.toggle_active_todo(),
+        "set_todo_cap" => match payload.as_deref().and_then(|p| p.trim().parse::<usize>().ok()) {
+            Some(cap) if (1..=MAX_TODO_CAP).contains(&cap) => {
+                app.set_todo_cap(cap);
+                app.status_message = Some(format!("Max tasks: {cap}"));
+            }
+            _ => {
+                app.status_message = Some(format!("Max tasks must be between 1 and {MAX_TODO_CAP}"))
+            }
+        },
         "complete_session" => app.show_complete_session_modal(),
         "confirm_modal" => app.confirm_modal(),
+        "complete_keep_unfinished" => app.confirm_complete_session(true),
+        "complete_fresh" => app.confirm_complete_session(false),
+        "toggle_carry_over" => app.toggle_carry_over(),
+        "start_saving_history" => app.start_saving_history(),
+        "undo" => app.undo(),
+        "redo" => app.redo(),
         "dismiss_modal" => app.dismiss_modal(),
         "next_history" => app.next_history(),
         "prev_history" => app.prev_history(),
         "copy_markdown" => app.copy_markdown(),
+        "copy_note" => app.copy_current_note(),
+        "show_export" => app.show_export_modal(),
+        "cycle_copy_template" => app.cycle_copy_template(),
+        "export_template" => {
+            if let Some(index) = payload.as_deref().and_then(|p| p.parse::<usize>().ok()) {
+                app.confirm_export_template(index);
+            }
+        }
+        "export" => {
+            if let Some(format) = payload.as_deref().and_then(|p| p.parse::<Format>().ok()) {
+                app.confirm_export(format);
+            }
+        }
         "clear_notes" => app.show_clear_notes_modal(),
         "new_session" => app.show_new_session_modal(),
         "show_help" => app.show_help(),
+        "show_stats" => app.show_stats(),
         "toggle_history" => {
             let was_showing = app.show_history;
             app.toggle_history();
-            let _ = resize_for_history(&webview, &saved_height, was_showing, app.show_history);
+            let todo_count = app.active_note.todos.len();
+            let _ = resize_for_history(
+                &webview,
+                &saved_height,
+                was_showing,
+                app.show_history,
+                todo_count,
+            );
         }
         "edit_char" => {
+            // Accepts a single key or a whole pasted string
             if let Some(ref p) = payload {
                 if let InputMode::Editing(idx) = app.input_mode {
-                    if let Some(c) = p.chars().next() {
-                        app.edit_char(c, idx);
-                    }
+                    app.edit_str(p, idx);
                 }
             }
         }
@@ -180,59 +423,155 @@ fn action(
                 app.edit_backspace(idx);
             }
         }
+        "edit_delete" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_delete(idx);
+            }
+        }
+        "edit_delete_word" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_delete_word(idx);
+            }
+        }
+        "edit_delete_to_start" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_delete_to_start(idx);
+            }
+        }
         "edit_clear" => {
             if let InputMode::Editing(idx) = app.input_mode {
                 app.edit_clear(idx);
             }
         }
+        "cursor_left" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_cursor_left(idx);
+            }
+        }
+        "cursor_right" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_cursor_right(idx);
+            }
+        }
+        "cursor_home" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_cursor_home(idx);
+            }
+        }
+        "cursor_end" => {
+            if let InputMode::Editing(idx) = app.input_mode {
+                app.edit_cursor_end(idx);
+            }
+        }
         "clear_sound" => {
             app.sound_pending = false;
         }
         _ => {}
     }
+    if app.active_note.todos.len() != todo_count {
+        let _ = resize_for_tasks(&webview, app.show_history, app.active_note.todos.len());
+    }
     snapshot(&mut app)
 }
 
+/// Fixed part of the compact height: title + timer + task box chrome + action bar.
+const COMPACT_BASE_HEIGHT: f64 = 192.0;
+/// Height of one task row.
+const TODO_ROW_HEIGHT: f64 = 22.0;
+/// Extra height the history pane needs on top of the compact layout.
+const HISTORY_PANE_HEIGHT: f64 = 140.0;
+const MIN_WIDTH: f64 = 600.0;
+
 /// Compact height: just title + timer + tasks + action bar (no history).
-const COMPACT_HEIGHT: f64 = 280.0;
+fn compact_height(todo_count: usize) -> f64 {
+    COMPACT_BASE_HEIGHT + todo_count as f64 * TODO_ROW_HEIGHT
+}
+
 /// Minimum window height when history is visible (compact + history pane).
-const HISTORY_MIN_HEIGHT: f64 = 420.0;
-const MIN_WIDTH: f64 = 600.0;
+fn history_min_height(todo_count: usize) -> f64 {
+    compact_height(todo_count) + HISTORY_PANE_HEIGHT
+}
 
 fn resize_for_history(
     webview: &WebviewWindow,
     saved_height: &State<'_, SavedWindowHeight>,
     was_showing: bool,
     now_showing: bool,
+    todo_count: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if was_showing && !now_showing {
         let scale = webview.scale_factor()?;
         let outer = webview.outer_size()?;
         let current_height = outer.height as f64 / scale;
         *saved_height.0.lock().unwrap() = Some(current_height);
-        webview.set_min_size(Some(LogicalSize::new(MIN_WIDTH, 300.0)))?;
-        webview.set_size(LogicalSize::new(outer.width as f64 / scale, COMPACT_HEIGHT))?;
+        let compact = compact_height(todo_count);
+        webview.set_min_size(Some(LogicalSize::new(MIN_WIDTH, compact)))?;
+        webview.set_size(LogicalSize::new(outer.width as f64 / scale, compact))?;
     } else if !was_showing && now_showing {
-        webview.set_min_size(Some(LogicalSize::new(MIN_WIDTH, HISTORY_MIN_HEIGHT)))?;
+        let min_height = history_min_height(todo_count);
+        webview.set_min_size(Some(LogicalSize::new(MIN_WIDTH, min_height)))?;
         let mut saved = saved_height.0.lock().unwrap();
         if let Some(h) = saved.take() {
             let scale = webview.scale_factor()?;
             let outer = webview.outer_size()?;
-            let restore_height = h.max(HISTORY_MIN_HEIGHT);
+            let restore_height = h.max(min_height);
             webview.set_size(LogicalSize::new(outer.width as f64 / scale, restore_height))?;
         }
     }
     Ok(())
 }
 
+/// Refit the window after the number of task rows changed.
+fn resize_for_tasks(
+    webview: &WebviewWindow,
+    show_history: bool,
+    todo_count: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let scale = webview.scale_factor()?;
+    let outer = webview.outer_size()?;
+    let width = outer.width as f64 / scale;
+    if show_history {
+        let min_height = history_min_height(todo_count);
+        webview.set_min_size(Some(LogicalSize::new(MIN_WIDTH, min_height)))?;
+        let height = outer.height as f64 / scale;
+        if height < min_height {
+            webview.set_size(LogicalSize::new(width, min_height))?;
+        }
+    } else {
+        let compact = compact_height(todo_count);
+        webview.set_min_size(Some(LogicalSize::new(MIN_WIDTH, compact)))?;
+        webview.set_size(LogicalSize::new(width, compact))?;
+    }
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Shares the TUI's history file; saving stays on once either app opted in
+    let mut initial = match HistoryStore::existing() {
+        Some(store) => App::new().with_store(store),
+        None => App::new(),
+    };
+    if let Some(file) = RecoveryFile::open_default() {
+        initial = initial.with_recovery(file);
+    }
+    if let Some(dir) = template::default_dir() {
+        match template::load_dir(&dir) {
+            Ok(templates) => initial.templates = templates,
+            Err(e) => initial.status_message = Some(format!("Template error: {e:#}")),
+        }
+    }
+
     tauri::Builder::default()
-        .manage(AppState(Mutex::new(App::new())))
+        .manage(AppState(Mutex::new(initial)))
         .manage(SavedWindowHeight(Mutex::new(None)))
         .manage(TrayHolder(Mutex::new(None)))
-        .invoke_handler(tauri::generate_handler![get_state, tick, action])
+        .invoke_handler(tauri::generate_handler![get_state, get_stats, tick, action])
         .setup(|app| {
+            if let Ok(dir) = app.path().download_dir() {
+                app.state::<AppState>().0.lock().unwrap().export_dir = dir;
+            }
+
             let toggle_timer =
                 MenuItemBuilder::with_id("toggle_timer", "Start / Pause").build(app)?;
             let show_hide =
@@ -249,7 +588,7 @@ pub fn run() {
             let tray = TrayIconBuilder::with_id("main-tray")
                 .icon(icon)
                 .menu(&menu)
-                .title("\u{25cb} 60m")
+                .title(format!("\u{25cb} {} {}m", Phase::Focus.label(), DEFAULT_DURATION / 60))
                 .tooltip("One Good Hour")
                 .on_menu_event(|app, event| match event.id().as_ref() {
                     "toggle_timer" => {